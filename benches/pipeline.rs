@@ -0,0 +1,41 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use lox::interpret::Interpreter;
+use lox::scanner::Scanner;
+use lox::syntax::Parser;
+
+const PROGRAM: &str = include_str!("lox/program.lox");
+const FIB: &str = include_str!("lox/fib.lox");
+const STRINGS: &str = include_str!("lox/strings.lox");
+
+fn scan(c: &mut Criterion) {
+    c.bench_function("scan program.lox", |b| {
+        b.iter(|| Scanner::new(PROGRAM.to_string()).scan_tokens().unwrap())
+    });
+}
+
+fn parse(c: &mut Criterion) {
+    let tokens = Scanner::new(PROGRAM.to_string()).scan_tokens().unwrap();
+    c.bench_function("parse program.lox", |b| {
+        b.iter_batched(
+            || tokens.clone(),
+            |tokens| Parser::new(tokens).parse().unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn interpret(c: &mut Criterion) {
+    for (name, source) in [("fib.lox", FIB), ("strings.lox", STRINGS)] {
+        let statements = lox::parse(source).unwrap();
+        c.bench_function(&format!("interpret {name}"), |b| {
+            b.iter_batched(
+                Interpreter::new,
+                |mut interpreter| interpreter.execute_all(&statements, &mut vec![]).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, scan, parse, interpret);
+criterion_main!(benches);
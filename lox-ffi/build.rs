@@ -0,0 +1,15 @@
+//! Regenerates `lox.h` from `src/lib.rs`, so the committed header always
+//! matches the functions it declares.
+use std::path::Path;
+
+fn main() {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").expect("set by cargo");
+    let dir = Path::new(&dir);
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let config =
+        cbindgen::Config::from_file(dir.join("cbindgen.toml")).expect("cbindgen.toml is valid");
+    cbindgen::generate_with_config(dir, config)
+        .expect("lox-ffi's API can be expressed in C")
+        .write_to_file(dir.join("lox.h"));
+}
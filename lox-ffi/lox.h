@@ -0,0 +1,62 @@
+#ifndef LOX_H
+#define LOX_H
+
+/* Generated by cbindgen from lox-ffi/src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// An interpreter whose variables persist across calls to `lox_run_string`.
+typedef struct lox_interpreter_t lox_interpreter_t;
+
+// The outcome of running a piece of source code.
+typedef struct lox_result_t lox_result_t;
+
+// Creates an interpreter. Free it with `lox_interpreter_free`.
+struct lox_interpreter_t *lox_interpreter_new(void);
+
+// Frees an interpreter created by `lox_interpreter_new`. Passing NULL is a
+// no-op.
+//
+// # Safety
+//
+// `interp` must be NULL or a pointer returned by `lox_interpreter_new` that
+// has not already been freed.
+void lox_interpreter_free(struct lox_interpreter_t *interp);
+
+// Runs a NUL-terminated, UTF-8 Lox program. The returned result is never
+// NULL and must be freed with `lox_result_free`. A panic inside the
+// interpreter comes back as an error rather than unwinding into C.
+//
+// # Safety
+//
+// `interp` must be a live pointer from `lox_interpreter_new`, and `source`
+// must point to a NUL-terminated string.
+struct lox_result_t *lox_run_string(struct lox_interpreter_t *interp, const char *source);
+
+// Returns true if the program ran without errors, or false for NULL.
+//
+// # Safety
+//
+// `result` must be NULL or a live pointer from `lox_run_string`.
+bool lox_result_is_ok(const struct lox_result_t *result);
+
+// Returns the error message, or NULL if the program succeeded or `result`
+// is NULL. The string is owned by `result` and is freed along with it.
+//
+// # Safety
+//
+// `result` must be NULL or a live pointer from `lox_run_string`.
+const char *lox_result_error_message(const struct lox_result_t *result);
+
+// Frees a result returned by `lox_run_string`. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `result` must be NULL or a pointer from `lox_run_string` that has not
+// already been freed.
+void lox_result_free(struct lox_result_t *result);
+
+#endif  /* LOX_H */
@@ -0,0 +1,129 @@
+//! A C API for embedding the Lox interpreter.
+//!
+//! The header `lox.h` is generated from this file by `build.rs`, using the
+//! settings in `cbindgen.toml`, each time the crate is built.
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use lox::error::Result;
+use lox::Lox;
+
+/// An interpreter whose variables persist across calls to `lox_run_string`.
+pub struct LoxInterpreter {
+    lox: Lox,
+}
+
+/// The outcome of running a piece of source code.
+pub struct LoxResult {
+    error: Option<CString>,
+}
+
+/// Creates an interpreter. Free it with `lox_interpreter_free`.
+#[no_mangle]
+pub extern "C" fn lox_interpreter_new() -> *mut LoxInterpreter {
+    Box::into_raw(Box::new(LoxInterpreter { lox: Lox::new() }))
+}
+
+/// Frees an interpreter created by `lox_interpreter_new`. Passing NULL is a
+/// no-op.
+///
+/// # Safety
+///
+/// `interp` must be NULL or a pointer returned by `lox_interpreter_new` that
+/// has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn lox_interpreter_free(interp: *mut LoxInterpreter) {
+    if !interp.is_null() {
+        drop(Box::from_raw(interp));
+    }
+}
+
+/// Runs a NUL-terminated, UTF-8 Lox program. The returned result is never
+/// NULL and must be freed with `lox_result_free`. A panic inside the
+/// interpreter comes back as an error rather than unwinding into C.
+///
+/// # Safety
+///
+/// `interp` must be a live pointer from `lox_interpreter_new`, and `source`
+/// must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn lox_run_string(
+    interp: *mut LoxInterpreter,
+    source: *const c_char,
+) -> *mut LoxResult {
+    let outcome = if interp.is_null() || source.is_null() {
+        Err("lox_run_string called with a NULL argument.".to_string())
+    } else {
+        match CStr::from_ptr(source).to_str() {
+            Ok(source) => run_guarded(&mut (*interp).lox, source),
+            Err(_) => Err("Source is not valid UTF-8.".to_string()),
+        }
+    };
+    let error = outcome
+        .err()
+        .map(|message| CString::new(message.replace('\0', "")).unwrap_or_default());
+    Box::into_raw(Box::new(LoxResult { error }))
+}
+
+/// Returns true if the program ran without errors, or false for NULL.
+///
+/// # Safety
+///
+/// `result` must be NULL or a live pointer from `lox_run_string`.
+#[no_mangle]
+pub unsafe extern "C" fn lox_result_is_ok(result: *const LoxResult) -> bool {
+    !result.is_null() && (*result).error.is_none()
+}
+
+/// Returns the error message, or NULL if the program succeeded or `result`
+/// is NULL. The string is owned by `result` and is freed along with it.
+///
+/// # Safety
+///
+/// `result` must be NULL or a live pointer from `lox_run_string`.
+#[no_mangle]
+pub unsafe extern "C" fn lox_result_error_message(result: *const LoxResult) -> *const c_char {
+    match result.as_ref().and_then(|result| result.error.as_ref()) {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Frees a result returned by `lox_run_string`. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `result` must be NULL or a pointer from `lox_run_string` that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn lox_result_free(result: *mut LoxResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+fn run(lox: &mut Lox, source: &str) -> Result<()> {
+    let result = lox.run_string(source);
+    for warning in lox.take_warnings() {
+        eprintln!("{warning}");
+    }
+    result
+}
+
+/// Runs `source`, turning a panic into an error message. The interpreter
+/// may be left partway through a run after a panic, but it is still safe
+/// to use or free.
+fn run_guarded(lox: &mut Lox, source: &str) -> std::result::Result<(), String> {
+    match panic::catch_unwind(AssertUnwindSafe(|| run(lox, source))) {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(payload) => {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown cause");
+            Err(format!("Interpreter panicked: {reason}"))
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::ffi::{CStr, CString};
+
+use lox_ffi::*;
+
+fn run(interp: *mut LoxInterpreter, source: &str) -> Option<String> {
+    let source = CString::new(source).unwrap();
+    unsafe {
+        let result = lox_run_string(interp, source.as_ptr());
+        let message = if lox_result_is_ok(result) {
+            assert!(lox_result_error_message(result).is_null());
+            None
+        } else {
+            let message = CStr::from_ptr(lox_result_error_message(result));
+            Some(message.to_str().unwrap().to_string())
+        };
+        lox_result_free(result);
+        message
+    }
+}
+
+#[test]
+fn state_persists_between_runs() {
+    let interp = lox_interpreter_new();
+    assert_eq!(run(interp, "var a = 1;"), None);
+    assert_eq!(run(interp, "a = a + 1;"), None);
+    assert_eq!(
+        run(interp, "print b;").as_deref(),
+        Some("[line 1] Error: Undefined variable 'b'.")
+    );
+    unsafe { lox_interpreter_free(interp) };
+}
+
+#[test]
+fn reports_syntax_errors() {
+    let interp = lox_interpreter_new();
+    assert_eq!(
+        run(interp, "print 1").as_deref(),
+        Some("[line 1] Error: Expected ';' after value.")
+    );
+    unsafe { lox_interpreter_free(interp) };
+}
+
+#[test]
+fn null_arguments_are_errors() {
+    unsafe {
+        let result = lox_run_string(std::ptr::null_mut(), std::ptr::null());
+        assert!(!lox_result_is_ok(result));
+        lox_result_free(result);
+        assert!(!lox_result_is_ok(std::ptr::null()));
+        assert!(lox_result_error_message(std::ptr::null()).is_null());
+        lox_result_free(std::ptr::null_mut());
+        lox_interpreter_free(std::ptr::null_mut());
+    }
+}
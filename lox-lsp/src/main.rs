@@ -0,0 +1,276 @@
+//! A language server for Lox, speaking JSON-RPC over stdin and stdout.
+//!
+//! Positions use the interpreter's byte-based columns, which match the
+//! protocol's UTF-16 columns as long as a line is ASCII.
+use std::collections::HashMap;
+
+use lox::error::Error;
+use lox::scanner::{keywords, Scanner, Token, TokenType};
+use lox::syntax::Parser;
+use tokio::sync::Mutex;
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+
+struct Backend {
+    client: Client,
+    documents: Mutex<HashMap<Url, String>>,
+}
+
+impl Backend {
+    async fn publish_diagnostics(&self, uri: Url) {
+        let Some(text) = self.documents.lock().await.get(&uri).cloned() else {
+            return;
+        };
+        let diagnostics = diagnose(&text).into_iter().collect();
+        self.client
+            .publish_diagnostics(uri, diagnostics, None)
+            .await;
+    }
+
+    async fn tokens(&self, uri: &Url) -> Option<Vec<Token>> {
+        let text = self.documents.lock().await.get(uri).cloned()?;
+        Scanner::new(text).scan_tokens().ok()
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Options(
+                    TextDocumentSyncOptions {
+                        open_close: Some(true),
+                        change: Some(TextDocumentSyncKind::FULL),
+                        save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                        ..Default::default()
+                    },
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions::default()),
+                ..Default::default()
+            },
+            server_info: Some(ServerInfo {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+        })
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents
+            .lock()
+            .await
+            .insert(uri.clone(), params.text_document.text);
+        self.publish_diagnostics(uri).await;
+    }
+
+    async fn did_change(&self, mut params: DidChangeTextDocumentParams) {
+        if let Some(change) = params.content_changes.pop() {
+            self.documents
+                .lock()
+                .await
+                .insert(params.text_document.uri, change.text);
+        }
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        self.publish_diagnostics(params.text_document.uri).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents
+            .lock()
+            .await
+            .remove(&params.text_document.uri);
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params.position;
+        let uri = &params.text_document_position_params.text_document.uri;
+        let Some(tokens) = self.tokens(uri).await else {
+            return Ok(None);
+        };
+        let hover = tokens
+            .iter()
+            .find(|token| token_range(token).is_some_and(|range| contains(range, position)))
+            .and_then(|token| {
+                Some(Hover {
+                    contents: HoverContents::Scalar(MarkedString::String(describe(token)?)),
+                    range: token_range(token),
+                })
+            });
+        Ok(hover)
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let mut keywords: Vec<_> = keywords().collect();
+        keywords.sort_unstable();
+        let mut items: Vec<_> = keywords
+            .into_iter()
+            .map(|keyword| CompletionItem {
+                label: keyword.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                ..Default::default()
+            })
+            .collect();
+
+        // Without a resolver, offer every variable declared in the document.
+        let tokens = self.tokens(uri).await.unwrap_or_default();
+        let mut names: Vec<_> = tokens
+            .windows(2)
+            .filter(|pair| pair[0].ty() == &TokenType::Var)
+            .filter_map(|pair| match pair[1].ty() {
+                TokenType::Identifier(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        items.extend(names.into_iter().map(|name| CompletionItem {
+            label: name,
+            kind: Some(CompletionItemKind::VARIABLE),
+            ..Default::default()
+        }));
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+}
+
+/// Scans and parses `text`, turning the first error into a diagnostic that
+/// covers the line it was reported on.
+fn diagnose(text: &str) -> Option<Diagnostic> {
+    let error = Scanner::new(text.to_string())
+        .scan_tokens()
+        .and_then(|tokens| Parser::new(tokens).parse())
+        .err()?;
+    Some(diagnostic(text, &error))
+}
+
+/// An error without a line of its own, which the scanner and parser are not
+/// expected to report, goes on the first line rather than going unseen.
+fn diagnostic(text: &str, error: &Error) -> Diagnostic {
+    let (line, message) = match error {
+        Error::Syntax { line, message, .. }
+        | Error::UnexpectedEof { line, message, .. }
+        | Error::Scan { line, message, .. } => (*line, message.to_string()),
+        Error::UnexpectedToken {
+            line,
+            found,
+            message,
+            ..
+        } => (*line, format!("{message} Found {found}.")),
+        error => (1, error.to_string()),
+    };
+    let line = line.saturating_sub(1) as u32;
+    let width = text.lines().nth(line as usize).map_or(0, str::len) as u32;
+    Diagnostic {
+        range: Range::new(Position::new(line, 0), Position::new(line, width)),
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("lox".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn describe(token: &Token) -> Option<String> {
+    let description = match token.ty() {
+        TokenType::Number(_) => "number".to_string(),
+        TokenType::String(_) | TokenType::RawString(_) => "string".to_string(),
+        TokenType::True | TokenType::False => "boolean".to_string(),
+        TokenType::Nil => "nil".to_string(),
+        TokenType::Identifier(name) => format!("variable `{name}`"),
+        _ if keywords().any(|keyword| keyword == token.lexeme()) => {
+            format!("keyword `{}`", token.lexeme())
+        }
+        _ => return None,
+    };
+    Some(description)
+}
+
+fn token_range(token: &Token) -> Option<Range> {
+    if token.lexeme().contains('\n') || token.ty() == &TokenType::Eof {
+        return None;
+    }
+    let line = token.line().checked_sub(1)? as u32;
+    let start = token.column().checked_sub(1)? as u32;
+    let end = start + token.lexeme().len() as u32;
+    Some(Range::new(
+        Position::new(line, start),
+        Position::new(line, end),
+    ))
+}
+
+fn contains(range: Range, position: Position) -> bool {
+    range.start <= position && position < range.end
+}
+
+#[tokio::main]
+async fn main() {
+    let (service, socket) = LspService::new(|client| Backend {
+        client,
+        documents: Mutex::new(HashMap::new()),
+    });
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_source_has_no_diagnostic() {
+        assert_eq!(diagnose("var a = 1;\nprint a;\n"), None);
+    }
+
+    #[test]
+    fn syntax_error_covers_its_line() {
+        let diagnostic = diagnose("var a = 1;\nprint @;\n").unwrap();
+        assert_eq!(diagnostic.range.start, Position::new(1, 0));
+        assert_eq!(diagnostic.range.end, Position::new(1, 8));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::ERROR));
+    }
+
+    #[test]
+    fn unexpected_token_names_what_was_found() {
+        let diagnostic = diagnose("var 1 = 2;").unwrap();
+        assert_eq!(diagnostic.range.start.line, 0);
+        assert!(
+            diagnostic.message.ends_with("Found number."),
+            "{}",
+            diagnostic.message
+        );
+    }
+
+    #[test]
+    fn other_errors_go_on_the_first_line() {
+        let diagnostic = diagnostic("print 1;\nprint 2;\n", &Error::Timeout);
+        assert_eq!(
+            diagnostic.range,
+            Range::new(Position::new(0, 0), Position::new(0, 8))
+        );
+        assert_eq!(diagnostic.message, Error::Timeout.to_string());
+    }
+
+    #[test]
+    fn hover_describes_the_token_under_the_cursor() {
+        let tokens = Scanner::new("var answer = 42;".to_string())
+            .scan_tokens()
+            .unwrap();
+        let token = tokens
+            .iter()
+            .find(|token| {
+                token_range(token).is_some_and(|range| contains(range, Position::new(0, 6)))
+            })
+            .unwrap();
+        assert_eq!(describe(token).as_deref(), Some("variable `answer`"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Procedural macros for defining Lox native functions in Rust.
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Error, FnArg, ItemFn, LitInt, LitStr, ReturnType};
+
+/// Declares a Rust function as a Lox native function, defined in every
+/// interpreter the program creates.
+///
+/// The function must have the signature `fn(Vec<Value>) -> Result<Value>`.
+/// Alongside it the attribute defines a constant
+/// [`NativeFunction`](../lox/callable/struct.NativeFunction.html) named
+/// after the function in upper case, for passing to
+/// `Interpreter::define_native` again after it has been replaced:
+///
+/// ```ignore
+/// #[lox_native(name = "sqrt", arity = 1)]
+/// fn sqrt(arguments: Vec<Value>) -> Result<Value> { ... }
+/// ```
+///
+/// `name` is the name Lox code calls it by, and defaults to the Rust name.
+/// `arity` is the number of arguments it takes, and must be given.
+#[proc_macro_attribute]
+pub fn lox_native(args: TokenStream, item: TokenStream) -> TokenStream {
+    let function = parse_macro_input!(item as ItemFn);
+    let mut name = None;
+    let mut arity = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse::<LitStr>()?);
+            Ok(())
+        } else if meta.path.is_ident("arity") {
+            arity = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<usize>()?);
+            Ok(())
+        } else {
+            Err(meta.error("expected `name` or `arity`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+
+    if let Err(error) = check_signature(&function) {
+        return error.to_compile_error().into();
+    }
+    let Some(arity) = arity else {
+        return Error::new_spanned(
+            &function.sig.ident,
+            "missing `arity`, the number of arguments the native takes",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let ident = &function.sig.ident;
+    let visibility = &function.vis;
+    let constant = format_ident!("{}", ident.to_string().to_uppercase());
+    let name = name.unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+    quote! {
+        #function
+
+        #visibility const #constant: ::lox::callable::NativeFunction =
+            ::lox::callable::NativeFunction::new(#name, #arity, #ident);
+
+        ::lox::inventory::submit! { #constant }
+    }
+    .into()
+}
+
+/// Rejects anything that cannot be a plain `fn(Vec<Value>) -> Result<Value>`.
+/// The argument and return types themselves are checked by the compiler when
+/// the function is passed to `NativeFunction::new`.
+fn check_signature(function: &ItemFn) -> syn::Result<()> {
+    let sig = &function.sig;
+    if sig.asyncness.is_some() {
+        return Err(Error::new_spanned(
+            sig.asyncness,
+            "native functions cannot be async",
+        ));
+    }
+    if !sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &sig.generics,
+            "native functions cannot be generic",
+        ));
+    }
+    if sig.inputs.len() != 1 {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            "native functions take a single `Vec<Value>` of arguments",
+        ));
+    }
+    if let Some(FnArg::Receiver(receiver)) = sig.inputs.first() {
+        return Err(Error::new_spanned(
+            receiver,
+            "native functions cannot take `self`",
+        ));
+    }
+    if let ReturnType::Default = sig.output {
+        return Err(Error::new_spanned(
+            sig,
+            "native functions must return `Result<Value>`",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use lox::callable::Callable;
+use lox::error::{Error, Result};
+use lox::interpret::{Interpreter, Value};
+use lox_macros::lox_native;
+
+#[lox_native(name = "sqrt", arity = 1)]
+fn square_root(arguments: Vec<Value>) -> Result<Value> {
+    match arguments[0] {
+        Value::Number(n) => Ok(Value::Number(n.sqrt())),
+        _ => Err(Error::TypeError {
+            file: None,
+            line: 0,
+            message: "Argument must be a number.".to_string(),
+        }),
+    }
+}
+
+#[lox_native(arity = 0)]
+fn answer(_arguments: Vec<Value>) -> Result<Value> {
+    Ok(Value::Number(42.0))
+}
+
+fn run(interpreter: &mut Interpreter, source: &str) -> Result<()> {
+    interpreter.execute_all(&lox::parse(source)?, &mut vec![])
+}
+
+#[test]
+fn the_attribute_declares_a_native_function() {
+    assert_eq!(SQUARE_ROOT.name(), "sqrt");
+    assert_eq!(SQUARE_ROOT.arity(), 1);
+    assert_eq!(ANSWER.name(), "answer");
+    assert_eq!(ANSWER.arity(), 0);
+    assert_eq!(
+        square_root(vec![Value::Number(9.0)]).unwrap(),
+        Value::Number(3.0)
+    );
+}
+
+#[test]
+fn declared_natives_are_callable_from_lox() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "var root = sqrt(answer() - 26);").unwrap();
+    assert_eq!(interpreter.export_globals()["root"], Value::Number(4.0));
+
+    assert!(matches!(
+        run(&mut interpreter, "sqrt();"),
+        Err(Error::Arity {
+            expected: 1,
+            got: 0,
+            ..
+        })
+    ));
+
+    interpreter.reset();
+    run(&mut interpreter, "var again = sqrt(16);").unwrap();
+    assert_eq!(interpreter.export_globals()["again"], Value::Number(4.0));
+}
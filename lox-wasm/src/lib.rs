@@ -0,0 +1,57 @@
+//! Bindings for running Lox in the browser, built from this directory with
+//!
+//! ```sh
+//! wasm-pack build --target web
+//! ```
+//!
+//! `demo/index.html` loads the result. Scripts there cannot read files,
+//! since `read_file_bytes` is left out on wasm32 and `import` fails.
+use wasm_bindgen::prelude::*;
+
+use lox::Lox;
+
+/// What a script printed, and what went wrong while running it.
+#[derive(Debug, Default, PartialEq)]
+pub struct Output {
+    /// Each line written by `print`.
+    pub stdout: Vec<String>,
+    /// The error that stopped the script, if any.
+    pub errors: Vec<String>,
+    /// Non-fatal diagnostics, as the command line prints after a run.
+    pub warnings: Vec<String>,
+}
+
+/// Runs `source` in a fresh session, capturing its output instead of
+/// writing it to stdout.
+pub fn run(source: &str) -> Output {
+    let mut lox = Lox::new();
+    let capture = lox.interpreter().capture_print();
+    let result = lox.run_string(source);
+    Output {
+        stdout: capture.output().lines().map(str::to_string).collect(),
+        errors: result.err().iter().map(ToString::to_string).collect(),
+        warnings: lox
+            .take_warnings()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+    }
+}
+
+/// Runs `source` like [`run`], returning an object with `stdout`, `errors`
+/// and `warnings` arrays of strings, ready for `JSON.stringify`.
+#[wasm_bindgen]
+pub fn run_lox(source: &str) -> JsValue {
+    let output = run(source);
+    let object = js_sys::Object::new();
+    for (key, lines) in [
+        ("stdout", output.stdout),
+        ("errors", output.errors),
+        ("warnings", output.warnings),
+    ] {
+        let array: js_sys::Array = lines.into_iter().map(JsValue::from).collect();
+        js_sys::Reflect::set(&object, &key.into(), &array)
+            .expect("a new object accepts any property");
+    }
+    object.into()
+}
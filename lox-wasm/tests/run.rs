@@ -0,0 +1,15 @@
+use lox_wasm::run;
+
+#[test]
+fn runs_capture_output_and_errors() {
+    let output = run("print 1 + 2;\nprint true;\nprint nil + 1;");
+    assert_eq!(output.stdout, ["3", "true"]);
+    assert_eq!(
+        output.errors,
+        ["[line 3] Type error: Cannot add nil to number."]
+    );
+
+    let output = run("var x = 1; { var x = 2; }");
+    assert!(output.stdout.is_empty() && output.errors.is_empty());
+    assert_eq!(output.warnings.len(), 1);
+}
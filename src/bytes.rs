@@ -0,0 +1,96 @@
+//! Natives for raw binary data in [`Value::Bytes`]. `read_file_bytes` is
+//! only compiled with the `io` feature, so that scripts cannot read files
+//! unless the embedder opts in, and never for wasm32, which has no files.
+//!
+//! Errors are raised at line 0, which the call replaces with its own.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::callable::NativeFunction;
+use crate::error::{Error, Result};
+use crate::interpret::Value;
+
+pub(crate) fn natives() -> Vec<NativeFunction> {
+    vec![
+        #[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+        NativeFunction::new("read_file_bytes", 1, |arguments| {
+            let [path] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+            let Value::String(path) = path else {
+                return Err(type_error("string", &path));
+            };
+            Ok(new(std::fs::read(path).map_err(|e| Error::chain(0, e))?))
+        }),
+        NativeFunction::new("bytes_len", 1, |arguments| {
+            let [bytes] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+            Ok((into_bytes(bytes)?.borrow().len() as f64).into())
+        }),
+        NativeFunction::new("bytes_get", 2, |arguments| {
+            let [bytes, index] = <[Value; 2]>::try_from(arguments).expect("arity is checked");
+            let bytes = into_bytes(bytes)?;
+            let bytes = bytes.borrow();
+            let index = into_index(index, bytes.len())?;
+            Ok(f64::from(bytes[index]).into())
+        }),
+        NativeFunction::new("bytes_set", 3, |arguments| {
+            let [bytes, index, byte] = <[Value; 3]>::try_from(arguments).expect("arity is checked");
+            let bytes = into_bytes(bytes)?;
+            let mut bytes = bytes.borrow_mut();
+            let index = into_index(index, bytes.len())?;
+            let byte = byte.into_double(0)?;
+            if byte.fract() != 0.0 || !(0.0..=255.0).contains(&byte) {
+                return Err(Error::TypeError {
+                    file: None,
+                    line: 0,
+                    message: format!("Expected a byte from 0 to 255 but got {byte}."),
+                });
+            }
+            bytes[index] = byte as u8;
+            Ok(Value::Nil)
+        }),
+        NativeFunction::new("bytes_to_string", 1, |arguments| {
+            let [bytes] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+            let bytes = into_bytes(bytes)?.borrow().clone();
+            let string = String::from_utf8(bytes).map_err(|e| Error::chain(0, e))?;
+            Ok(string.into())
+        }),
+        NativeFunction::new("string_to_bytes", 1, |arguments| {
+            let [string] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+            match string {
+                Value::String(s) => Ok(new(s.into_bytes())),
+                value => Err(type_error("string", &value)),
+            }
+        }),
+    ]
+}
+
+fn new(bytes: Vec<u8>) -> Value {
+    Value::Bytes(Rc::new(RefCell::new(bytes)))
+}
+
+fn into_bytes(value: Value) -> Result<Rc<RefCell<Vec<u8>>>> {
+    match value {
+        Value::Bytes(bytes) => Ok(bytes),
+        value => Err(type_error("bytes", &value)),
+    }
+}
+
+/// A whole number less than `len`.
+fn into_index(value: Value, len: usize) -> Result<usize> {
+    let index = value.into_double(0)?;
+    if index.fract() != 0.0 || index < 0.0 || index >= len as f64 {
+        return Err(Error::TypeError {
+            file: None,
+            line: 0,
+            message: format!("Index {index} is out of range for {len} bytes."),
+        });
+    }
+    Ok(index as usize)
+}
+
+fn type_error(expected: &str, value: &Value) -> Error {
+    Error::TypeError {
+        file: None,
+        line: 0,
+        message: format!("Expected {expected} but got {}.", value.kind()),
+    }
+}
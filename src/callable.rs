@@ -0,0 +1,175 @@
+use std::cell::RefCell;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+
+use crate::environment::Environment;
+use crate::error::{Result, Warning};
+use crate::interpret::{Interpreter, Value};
+use crate::scanner::TokenType;
+use crate::syntax::Function;
+
+/// Anything that can be called from Lox code.
+///
+/// The interpreter checks the argument count against [`arity`](Self::arity)
+/// before calling, so implementations can rely on `arguments` having exactly
+/// that many values.
+pub trait Callable: Debug + Display {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Value>;
+
+    fn arity(&self) -> usize;
+
+    /// The name to show for the callable in a call stack, or `None` for an
+    /// anonymous function.
+    fn name(&self) -> Option<&str> {
+        None
+    }
+
+    /// The scope the callable captured, if any. The garbage collector
+    /// follows it to find the variables a closure keeps alive.
+    fn closure(&self) -> Option<&Rc<RefCell<Environment>>> {
+        None
+    }
+}
+
+/// A function declared in Lox, along with the scope it was declared in.
+#[derive(Debug)]
+pub struct LoxFunction {
+    declaration: Rc<Function>,
+    closure: Rc<RefCell<Environment>>,
+}
+
+impl LoxFunction {
+    pub fn new(declaration: Rc<Function>, closure: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            declaration,
+            closure,
+        }
+    }
+}
+
+impl Callable for LoxFunction {
+    fn call(
+        &self,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        let mut environment = Environment::new_enclosed_with_capacity(
+            Rc::clone(&self.closure),
+            self.declaration.params.len(),
+        );
+        for (param, argument) in self.declaration.params.iter().zip(arguments) {
+            environment.define(param.lexeme.clone(), argument);
+        }
+        interpreter.execute_body(&self.declaration.body, environment, warnings)
+    }
+
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self.declaration.name.ty {
+            TokenType::Fun => None,
+            _ => Some(&self.declaration.name.lexeme),
+        }
+    }
+
+    fn closure(&self) -> Option<&Rc<RefCell<Environment>>> {
+        Some(&self.closure)
+    }
+}
+
+impl Display for LoxFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.declaration.name.ty {
+            TokenType::Fun => write!(f, "<fn>"),
+            _ => write!(f, "<fn {}>", self.declaration.name.lexeme),
+        }
+    }
+}
+
+/// A function implemented in Rust.
+#[derive(Debug, Clone)]
+pub struct NativeFunction {
+    name: &'static str,
+    arity: usize,
+    function: fn(Vec<Value>) -> Result<Value>,
+}
+
+impl NativeFunction {
+    /// Usable in constants, which is how `#[lox_native]` declares natives.
+    pub const fn new(
+        name: &'static str,
+        arity: usize,
+        function: fn(Vec<Value>) -> Result<Value>,
+    ) -> Self {
+        Self {
+            name,
+            arity,
+            function,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Callable for NativeFunction {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+        _warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        (self.function)(arguments)
+    }
+
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(self.name)
+    }
+}
+
+// Natives declared with `#[lox_native]` anywhere in the program.
+inventory::collect!(NativeFunction);
+
+impl Display for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/// The time since the Unix epoch. `SystemTime::now` panics on wasm32 in the
+/// browser, so the browser's clock is used there instead.
+pub(crate) fn since_epoch() -> std::time::Duration {
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    return std::time::Duration::from_secs_f64(js_sys::Date::now() / 1000.0);
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// The functions every program starts with, followed by those declared
+/// with `#[lox_native]`.
+pub(crate) fn natives() -> Vec<NativeFunction> {
+    let mut natives = vec![
+        NativeFunction::new("clock", 0, |_| Ok(since_epoch().as_secs_f64().into())),
+        NativeFunction::new("clone", 1, |arguments| Ok(arguments[0].deep_clone())),
+    ];
+    natives.extend(crate::bytes::natives());
+    #[cfg(feature = "regex")]
+    natives.extend(crate::regexp::natives());
+    natives.extend(inventory::iter::<NativeFunction>.into_iter().cloned());
+    natives
+}
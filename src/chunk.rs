@@ -0,0 +1,254 @@
+use crate::error::{Error, Result};
+use crate::interpret::Value;
+
+/// Identifies serialized chunks, followed by [`FORMAT_VERSION`].
+const MAGIC: &[u8; 4] = b"LOXC";
+
+/// Bumped whenever the serialized layout or the meaning of an opcode
+/// changes, so that stale bytecode is rejected instead of misread.
+pub const FORMAT_VERSION: u16 = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(u8)]
+pub enum OpCode {
+    Constant,
+    Nil,
+    True,
+    False,
+    Pop,
+    Equal,
+    Greater,
+    Less,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+    Print,
+    Return,
+}
+
+impl TryFrom<u8> for OpCode {
+    type Error = u8;
+
+    fn try_from(byte: u8) -> std::result::Result<Self, Self::Error> {
+        use OpCode::*;
+        const OPCODES: [OpCode; 16] = [
+            Constant, Nil, True, False, Pop, Equal, Greater, Less, Add, Subtract, Multiply, Divide,
+            Not, Negate, Print, Return,
+        ];
+        OPCODES.get(byte as usize).copied().ok_or(byte)
+    }
+}
+
+impl OpCode {
+    /// How many values the instruction pops, then how many it pushes.
+    fn stack_effect(self) -> (usize, usize) {
+        use OpCode::*;
+        match self {
+            Constant | Nil | True | False => (0, 1),
+            Pop | Print | Return => (1, 0),
+            Equal | Greater | Less | Add | Subtract | Multiply | Divide => (2, 1),
+            Not | Negate => (1, 1),
+        }
+    }
+
+    /// How many operand bytes follow the opcode.
+    fn operands(self) -> usize {
+        match self {
+            OpCode::Constant => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// A sequence of bytecode along with the constants it refers to.
+#[derive(Clone, Debug, Default)]
+pub struct Chunk {
+    pub(crate) code: Vec<u8>,
+    pub(crate) constants: Vec<Value>,
+    pub(crate) lines: Vec<usize>,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, byte: u8, line: usize) {
+        self.code.push(byte);
+        self.lines.push(line);
+    }
+
+    pub fn write_op(&mut self, op: OpCode, line: usize) {
+        self.write(op as u8, line);
+    }
+
+    /// Adds `value` to the constant pool, returning its index.
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+}
+
+impl Chunk {
+    /// Serializes the chunk into the versioned format read by
+    /// [`Chunk::from_bytes`]. All integers are little-endian.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidBytecode`] if a constant is a function, a regular
+    /// expression or bytes, none of which the format can hold.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(FORMAT_VERSION.to_le_bytes());
+
+        bytes.extend((self.constants.len() as u32).to_le_bytes());
+        for constant in &self.constants {
+            match constant {
+                Value::Nil => bytes.push(0),
+                Value::Boolean(b) => bytes.extend([1, *b as u8]),
+                Value::Number(n) => {
+                    bytes.push(2);
+                    bytes.extend(n.to_le_bytes());
+                }
+                Value::String(s) => {
+                    bytes.push(3);
+                    bytes.extend((s.len() as u32).to_le_bytes());
+                    bytes.extend(s.as_bytes());
+                }
+                Value::Callable(_) => {
+                    return Err(Error::InvalidBytecode("Functions cannot be serialized."));
+                }
+                #[cfg(feature = "regex")]
+                Value::RegExp(_) => {
+                    return Err(Error::InvalidBytecode(
+                        "Regular expressions cannot be serialized.",
+                    ));
+                }
+                Value::Bytes(_) => {
+                    return Err(Error::InvalidBytecode("Bytes cannot be serialized."));
+                }
+            }
+        }
+
+        bytes.extend((self.code.len() as u32).to_le_bytes());
+        bytes.extend(&self.code);
+        for line in &self.lines {
+            bytes.extend((*line as u32).to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Reads a chunk written by [`Chunk::to_bytes`], checking that it was
+    /// written by this version and that every instruction is well formed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::InvalidBytecode("Missing header."));
+        }
+        let version = reader.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(Error::BytecodeVersion {
+                found: version,
+                expected: FORMAT_VERSION,
+            });
+        }
+
+        let mut chunk = Chunk::new();
+        for _ in 0..reader.u32()? {
+            let constant = match reader.u8()? {
+                0 => Value::Nil,
+                1 => Value::Boolean(reader.u8()? != 0),
+                2 => Value::Number(f64::from_le_bytes(reader.array()?)),
+                3 => {
+                    let len = reader.u32()? as usize;
+                    let s = std::str::from_utf8(reader.take(len)?)
+                        .map_err(|_| Error::InvalidBytecode("String constant is not UTF-8."))?;
+                    Value::String(s.to_string())
+                }
+                _ => return Err(Error::InvalidBytecode("Unknown constant type.")),
+            };
+            chunk.constants.push(constant);
+        }
+
+        let len = reader.u32()? as usize;
+        chunk.code = reader.take(len)?.to_vec();
+        for _ in 0..len {
+            chunk.lines.push(reader.u32()? as usize);
+        }
+        if !reader.0.is_empty() {
+            return Err(Error::InvalidBytecode("Trailing bytes after chunk."));
+        }
+
+        chunk.validate()?;
+        Ok(chunk)
+    }
+
+    /// Checks that the [`Vm`](crate::vm::Vm) can run the chunk without
+    /// reading past the end of the code, using a missing constant or popping
+    /// an empty stack. Instructions are decoded one after another, tracking
+    /// the stack depth, and the last one must be the only return.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.lines.len() != self.code.len() {
+            return Err(Error::InvalidBytecode(
+                "Line table does not match the code.",
+            ));
+        }
+        let mut offset = 0;
+        let mut depth = 0usize;
+        while offset < self.code.len() {
+            let op = OpCode::try_from(self.code[offset])
+                .map_err(|_| Error::InvalidBytecode("Unknown opcode."))?;
+            if op == OpCode::Constant {
+                let index = self.code.get(offset + 1).copied();
+                if index.is_none_or(|i| i as usize >= self.constants.len()) {
+                    return Err(Error::InvalidBytecode("Constant index out of range."));
+                }
+            }
+            let (pops, pushes) = op.stack_effect();
+            depth = depth
+                .checked_sub(pops)
+                .ok_or(Error::InvalidBytecode("Stack underflow."))?
+                + pushes;
+            offset += 1 + op.operands();
+            if op == OpCode::Return {
+                if offset != self.code.len() {
+                    return Err(Error::InvalidBytecode("Code after return."));
+                }
+                return Ok(());
+            }
+        }
+        Err(Error::InvalidBytecode("Chunk does not end with a return."))
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(Error::InvalidBytecode("Unexpected end of bytecode."));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().expect("took exactly N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
@@ -0,0 +1,136 @@
+use crate::chunk::{Chunk, OpCode};
+use crate::error::{Error, Result};
+use crate::interpret::Value;
+use crate::scanner::{Token, TokenType};
+use crate::syntax::Expr;
+
+/// Compiles a syntax tree into a [`Chunk`] of bytecode for the [`Vm`](crate::vm::Vm).
+#[derive(Default)]
+pub struct Compiler {
+    chunk: Chunk,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compile(mut self, expr: Expr) -> Result<Chunk> {
+        let line = self.expression(expr)?;
+        self.chunk.write_op(OpCode::Return, line);
+        Ok(self.chunk)
+    }
+
+    /// Emits the code for `expr`, returning the line it was found on.
+    fn expression(&mut self, expr: Expr) -> Result<usize> {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                self.expression(*left)?;
+                self.expression(*right)?;
+                self.binary(&operator);
+                Ok(operator.line)
+            }
+            Expr::Grouping(e) => self.expression(*e),
+            Expr::Literal(token) => {
+                match token.ty {
+                    TokenType::Number(num) => self.constant(num.into(), token.line)?,
+                    TokenType::String(s) | TokenType::RawString(s) => {
+                        self.constant(s.into(), token.line)?
+                    }
+                    TokenType::False => self.chunk.write_op(OpCode::False, token.line),
+                    TokenType::True => self.chunk.write_op(OpCode::True, token.line),
+                    TokenType::Nil => self.chunk.write_op(OpCode::Nil, token.line),
+                    _ => panic!("Invalid literal value"),
+                }
+                Ok(token.line)
+            }
+            Expr::Unary { operator, right } => {
+                self.expression(*right)?;
+                let op = match operator.ty {
+                    TokenType::Minus => OpCode::Negate,
+                    TokenType::Bang => OpCode::Not,
+                    _ => panic!("Invalid unary operator"),
+                };
+                self.chunk.write_op(op, operator.line);
+                Ok(operator.line)
+            }
+            Expr::Sequence { left, right } => {
+                let line = self.expression(*left)?;
+                self.chunk.write_op(OpCode::Pop, line);
+                self.expression(*right)
+            }
+            Expr::Logical { operator, .. } => Err(Error::Syntax {
+                file: operator.file(),
+                line: operator.line,
+                message: "Logical operators are not supported by the compiler yet.",
+            }),
+            Expr::Call { paren, .. } => Err(Error::Syntax {
+                file: paren.file(),
+                line: paren.line,
+                message: "Function calls are not supported by the compiler yet.",
+            }),
+            Expr::Variable(name) | Expr::Assign { name, .. } => Err(Error::Syntax {
+                file: name.file(),
+                line: name.line,
+                message: "Variables are not supported by the compiler yet.",
+            }),
+            Expr::Get { name, .. } => Err(Error::Syntax {
+                file: name.file(),
+                line: name.line,
+                message: "Properties are not supported by the compiler yet.",
+            }),
+            Expr::Cast { target_type, .. } => Err(Error::Syntax {
+                file: target_type.file(),
+                line: target_type.line,
+                message: "Casts are not supported by the compiler yet.",
+            }),
+            Expr::Lambda(function) => Err(Error::Syntax {
+                file: function.name.file(),
+                line: function.name.line,
+                message: "Functions are not supported by the compiler yet.",
+            }),
+        }
+    }
+
+    fn binary(&mut self, operator: &Token) {
+        let line = operator.line;
+        match operator.ty {
+            TokenType::Minus => self.chunk.write_op(OpCode::Subtract, line),
+            TokenType::Slash => self.chunk.write_op(OpCode::Divide, line),
+            TokenType::Star => self.chunk.write_op(OpCode::Multiply, line),
+            TokenType::Plus => self.chunk.write_op(OpCode::Add, line),
+            TokenType::Greater => self.chunk.write_op(OpCode::Greater, line),
+            TokenType::GreaterEqual => {
+                self.chunk.write_op(OpCode::Less, line);
+                self.chunk.write_op(OpCode::Not, line);
+            }
+            TokenType::Less => self.chunk.write_op(OpCode::Less, line),
+            TokenType::LessEqual => {
+                self.chunk.write_op(OpCode::Greater, line);
+                self.chunk.write_op(OpCode::Not, line);
+            }
+            TokenType::BangEqual => {
+                self.chunk.write_op(OpCode::Equal, line);
+                self.chunk.write_op(OpCode::Not, line);
+            }
+            TokenType::EqualEqual => self.chunk.write_op(OpCode::Equal, line),
+            _ => panic!("Invalid binary operator"),
+        }
+    }
+
+    fn constant(&mut self, value: Value, line: usize) -> Result<()> {
+        let index = self.chunk.add_constant(value);
+        let index = u8::try_from(index).map_err(|_| Error::Syntax {
+            file: None,
+            line,
+            message: "Too many constants in one chunk.",
+        })?;
+        self.chunk.write_op(OpCode::Constant, line);
+        self.chunk.write(index, line);
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use crate::chunk::{Chunk, OpCode};
+
+/// Prints every instruction in `chunk` under a `== name ==` header.
+pub fn disassemble(chunk: &Chunk, name: &str) {
+    println!("== {name} ==");
+    let mut offset = 0;
+    while offset < chunk.code.len() {
+        offset = disassemble_instruction(chunk, offset);
+    }
+}
+
+/// Prints the instruction at `offset`, returning the offset of the next one.
+///
+/// A chunk built by hand with [`Chunk::write`] may end in the middle of an
+/// instruction or name a constant it does not have; such an operand is
+/// printed as `<invalid>`.
+pub fn disassemble_instruction(chunk: &Chunk, offset: usize) -> usize {
+    print!("{offset:04} ");
+    if offset > 0 && chunk.lines[offset] == chunk.lines[offset - 1] {
+        print!("   | ");
+    } else {
+        print!("{:4} ", chunk.lines[offset]);
+    }
+
+    let byte = chunk.code[offset];
+    match OpCode::try_from(byte) {
+        Ok(OpCode::Constant) => {
+            let Some(&index) = chunk.code.get(offset + 1) else {
+                println!("{:<16} <invalid>", "Constant");
+                return offset + 1;
+            };
+            match chunk.constants.get(index as usize) {
+                Some(value) => println!("{:<16} {index:4} '{value}'", "Constant"),
+                None => println!("{:<16} {index:4} <invalid>", "Constant"),
+            }
+            offset + 2
+        }
+        Ok(op) => {
+            println!("{op:?}");
+            offset + 1
+        }
+        Err(byte) => {
+            println!("Unknown opcode {byte}");
+            offset + 1
+        }
+    }
+}
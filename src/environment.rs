@@ -0,0 +1,215 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::error::{Error, Result};
+use crate::gc::MemCounter;
+use crate::interpret::Value;
+use crate::scanner::Token;
+
+/// The variables bound in one scope, along with the scope enclosing it.
+/// Cloning copies this scope's bindings and shares the enclosing scope.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+    /// Read-only bindings consulted after every enclosing scope.
+    frozen: Option<FrozenEnvironment>,
+    /// Where the interpreter's memory usage is counted, once the scope is
+    /// on its heap.
+    usage: Option<Rc<MemCounter>>,
+}
+
+/// The copy is not on the interpreter's heap, so its variables are not
+/// counted.
+impl Clone for Environment {
+    fn clone(&self) -> Self {
+        Self {
+            values: self.values.clone(),
+            enclosing: self.enclosing.clone(),
+            frozen: self.frozen.clone(),
+            usage: None,
+        }
+    }
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        if let Some(usage) = &self.usage {
+            self.values.values().for_each(|value| usage.remove(value));
+            usage.free();
+        }
+    }
+}
+
+/// A read-only snapshot of bindings made by [`Environment::freeze`].
+///
+/// Cloning it shares the snapshot rather than copying it, so one prelude
+/// can back many environments. Values may hold `Rc`s, so the snapshot
+/// stays on one thread.
+#[derive(Debug, Clone)]
+pub struct FrozenEnvironment(Rc<HashMap<String, Value>>);
+
+impl FrozenEnvironment {
+    pub fn values(&self) -> &HashMap<String, Value> {
+        &self.0
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty scope with room for `capacity` variables before it needs to
+    /// grow.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut environment = Self::default();
+        environment.values = HashMap::with_capacity(capacity);
+        environment
+    }
+
+    pub fn new_enclosed(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self::new_enclosed_with_capacity(enclosing, 0)
+    }
+
+    /// Like [`new_enclosed`](Self::new_enclosed), with room for `capacity`
+    /// variables, such as a function's parameters.
+    pub fn new_enclosed_with_capacity(
+        enclosing: Rc<RefCell<Environment>>,
+        capacity: usize,
+    ) -> Self {
+        let mut environment = Self::with_capacity(capacity);
+        environment.enclosing = Some(enclosing);
+        environment
+    }
+
+    /// A scope that falls back to `frozen` for names it does not bind.
+    /// Assigning to one of those names binds it in this scope, leaving the
+    /// snapshot untouched.
+    pub fn new_with_frozen_globals(frozen: &FrozenEnvironment) -> Self {
+        let mut environment = Self::default();
+        environment.frozen = Some(frozen.clone());
+        environment
+    }
+
+    /// Snapshots every binding visible from this scope.
+    pub fn freeze(&self) -> FrozenEnvironment {
+        FrozenEnvironment(Rc::new(self.iter().collect()))
+    }
+
+    pub fn define(&mut self, name: impl Into<String>, value: Value) {
+        if let Some(usage) = &self.usage {
+            usage.add(&value);
+        }
+        if let Some(previous) = self.values.insert(name.into(), value) {
+            if let Some(usage) = &self.usage {
+                usage.remove(&previous);
+            }
+        }
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Value> {
+        if let Some(value) = self.values.get(&name.lexeme) {
+            Ok(value.clone())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().get(name)
+        } else if let Some(value) = self.frozen_value(&name.lexeme) {
+            Ok(value.clone())
+        } else {
+            Err(Error::UndefinedVariable {
+                file: name.file(),
+                line: name.line,
+                name: name.lexeme.clone(),
+            })
+        }
+    }
+
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<()> {
+        if let Some(slot) = self.values.get_mut(&name.lexeme) {
+            if let Some(usage) = &self.usage {
+                usage.add(&value);
+                usage.remove(slot);
+            }
+            *slot = value;
+            Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)
+        } else if self.frozen_value(&name.lexeme).is_some() {
+            self.define(name.lexeme.clone(), value);
+            Ok(())
+        } else {
+            Err(Error::UndefinedVariable {
+                file: name.file(),
+                line: name.line,
+                name: name.lexeme.clone(),
+            })
+        }
+    }
+
+    /// Every binding visible from this scope, innermost scope first and in
+    /// name order within each scope. Names hidden by an inner binding are
+    /// skipped.
+    ///
+    /// Enclosing scopes sit behind a `RefCell`, so the bindings are cloned.
+    pub fn iter(&self) -> impl Iterator<Item = (String, Value)> {
+        let mut bindings: Vec<(String, Value)> = vec![];
+        let mut seen = HashSet::new();
+        self.collect_visible(&mut bindings, &mut seen);
+        bindings.into_iter()
+    }
+
+    fn collect_visible(&self, bindings: &mut Vec<(String, Value)>, seen: &mut HashSet<String>) {
+        extend_sorted(bindings, seen, &self.values);
+        if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow().collect_visible(bindings, seen);
+        }
+        if let Some(frozen) = &self.frozen {
+            extend_sorted(bindings, seen, frozen.values());
+        }
+    }
+
+    fn frozen_value(&self, name: &str) -> Option<&Value> {
+        self.frozen.as_ref()?.values().get(name)
+    }
+
+    /// The bindings made directly in this scope, ignoring enclosing ones.
+    pub fn values(&self) -> &HashMap<String, Value> {
+        &self.values
+    }
+
+    pub(crate) fn enclosing(&self) -> Option<&Rc<RefCell<Environment>>> {
+        self.enclosing.as_ref()
+    }
+
+    /// Drops every binding and the link to the enclosing scope, so that the
+    /// garbage collector can break a cycle through this scope.
+    pub(crate) fn clear(&mut self) {
+        if let Some(usage) = &self.usage {
+            self.values.values().for_each(|value| usage.remove(value));
+        }
+        self.values.clear();
+        self.enclosing = None;
+    }
+
+    /// Counts this scope's variables in `usage`, now and as they change.
+    pub(crate) fn count_usage(&mut self, usage: Rc<MemCounter>) {
+        self.values.values().for_each(|value| usage.add(value));
+        self.usage = Some(usage);
+    }
+}
+
+/// Appends the bindings in `values` not already `seen`, in name order.
+fn extend_sorted(
+    bindings: &mut Vec<(String, Value)>,
+    seen: &mut HashSet<String>,
+    values: &HashMap<String, Value>,
+) {
+    let mut scope: Vec<_> = values
+        .iter()
+        .filter(|(name, _)| seen.insert(name.to_string()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    scope.sort_by(|(a, _), (b, _)| a.cmp(b));
+    bindings.extend(scope);
+}
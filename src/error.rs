@@ -1,20 +1,365 @@
+use std::path::{Path, PathBuf};
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Errors raised while running Lox code.
+///
+/// This implements [`std::error::Error`], with the underlying I/O error as
+/// its [`source`](std::error::Error::source), so it converts into
+/// `Box<dyn std::error::Error + Send + Sync>`, `anyhow::Error` and similar
+/// wrappers through their blanket `From` impls.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     #[error("{0}")]
     IO(#[from] std::io::Error),
-    #[error("[line {line}] Error: {message}")]
-    Syntax { line: usize, message: &'static str },
-    #[error("[line {line}] Error: {message}")]
-    TypeError { line: usize, message: &'static str },
+    /// Every error with a line also has a `file`, the name of the source it
+    /// was found in, such as a script's path or `<repl>`. Errors in sources
+    /// without a name, and those raised by Rust code before Lox reports
+    /// them, have `None`.
+    #[error("{} Error: {message}", at(.file, .line, None))]
+    Syntax {
+        file: Option<PathBuf>,
+        line: usize,
+        message: &'static str,
+    },
+    /// A syntax error at a token the parser did not expect. `found` is its
+    /// [`display_name`](crate::scanner::TokenType::display_name).
+    #[error("{} Error at {found}: {message}", at(.file, .line, Some(.column)))]
+    UnexpectedToken {
+        file: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        found: &'static str,
+        message: &'static str,
+    },
+    /// A syntax error caused by the input ending too soon, including a
+    /// string left open.
+    #[error("{} Error: {message}", at(.file, .line, None))]
+    UnexpectedEof {
+        file: Option<PathBuf>,
+        line: usize,
+        message: &'static str,
+    },
+    /// Text the scanner cannot make a token of.
+    #[error("{} Error: {message}", at(.file, .line, Some(.column)))]
+    Scan {
+        file: Option<PathBuf>,
+        line: usize,
+        column: usize,
+        message: &'static str,
+    },
+    /// An edit given to
+    /// [`Scanner::rescan_range`](crate::scanner::Scanner::rescan_range) for
+    /// lines the source does not have.
+    #[error("Error: Lines {start} to {end} are outside the source's {lines} lines.")]
+    LineRange {
+        start: usize,
+        end: usize,
+        lines: usize,
+    },
+    /// An operand or callee of the wrong type, naming the type found.
+    #[error("{} Type error: {message}", at(.file, .line, None))]
+    TypeError {
+        file: Option<PathBuf>,
+        line: usize,
+        message: String,
+    },
+    #[error("{} Error: Undefined variable '{name}'.", at(.file, .line, None))]
+    UndefinedVariable {
+        file: Option<PathBuf>,
+        line: usize,
+        name: String,
+    },
+    #[error("{} Error: Expected {expected} arguments but got {got}.", at(.file, .line, None))]
+    Arity {
+        file: Option<PathBuf>,
+        line: usize,
+        expected: usize,
+        got: usize,
+    },
+    /// A module named by an `import` could not be loaded. `path` is as
+    /// written in the `import` statement, and `file` is the source the
+    /// statement is in.
+    #[error("{} Error: {message} '{}'.", at(.file, .line, None), path.display())]
+    Import {
+        file: Option<PathBuf>,
+        line: usize,
+        path: PathBuf,
+        message: &'static str,
+    },
+    /// An error from Rust code called by Lox, such as a native function.
+    /// Made by [`Error::chain`].
+    #[error("{} Error: {source}", at(.file, .line, None))]
+    NativeError {
+        file: Option<PathBuf>,
+        line: usize,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    #[error("{} Error: The {limit} length limit of {max} was exceeded.", at(.file, .line, None))]
+    ResourceLimit {
+        file: Option<PathBuf>,
+        line: usize,
+        limit: &'static str,
+        max: usize,
+    },
+    /// Calls nested deeper than the interpreter allows, usually from
+    /// unbounded recursion. `line` is the call that went too deep.
+    #[error("{} Error: Stack overflow.", at(.file, .line, None))]
+    StackOverflow { file: Option<PathBuf>, line: usize },
+    #[error("Error: Execution timed out.")]
+    Timeout,
+    #[error("Error: Execution was interrupted.")]
+    Interrupted,
+    #[error("Invalid bytecode: {0}")]
+    InvalidBytecode(&'static str),
+    #[error("Bytecode format version {found} is not supported (expected {expected}).")]
+    BytecodeVersion { found: u16, expected: u16 },
+}
+
+/// Where an error was found: `[line 3]` in a source without a name, or
+/// `[file.lox:3]` in a named one, with the column after the line when it is
+/// known, as `[file.lox:3:14]`.
+fn at(file: &Option<PathBuf>, line: &usize, column: Option<&usize>) -> String {
+    match (file, column) {
+        (None, _) => format!("[line {line}]"),
+        (Some(file), None) => format!("[{}:{line}]", file.display()),
+        (Some(file), Some(column)) => format!("[{}:{line}:{column}]", file.display()),
+    }
+}
+
+/// The stage of running a program that an [`Error`] comes from, returned
+/// by [`Error::phase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading a script from disk.
+    IO,
+    /// Scanning source text into tokens, or editing it with
+    /// [`rescan_range`](crate::scanner::Scanner::rescan_range). A string left
+    /// open at the end is [`Error::UnexpectedEof`], which counts as
+    /// [`Parse`](Self::Parse) like any other input that ends too soon.
+    Scan,
+    /// Parsing tokens into statements, or compiling them to bytecode.
+    Parse,
+    /// Resolving variables before running. There is no resolver yet, so no
+    /// error has this phase.
+    Resolve,
+    /// Running the program, including loading its imports.
+    Runtime,
+}
+
+/// Lets `?` pass Lox errors up through functions returning `io::Result`.
+/// An I/O error is unwrapped; any other error becomes
+/// [`ErrorKind::Other`](std::io::ErrorKind::Other) with the Lox error as its
+/// inner error.
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::IO(error) => error,
+            error => std::io::Error::other(error),
+        }
+    }
+}
+
+/// A diagnostic that is reported to the user but does not abort execution.
+/// It is shown with the same location prefix as an [`Error`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub file: Option<PathBuf>,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let at = at(&self.file, &self.line, Some(&self.column));
+        write!(f, "{at} Warning: {}", self.message)
+    }
 }
 
-#[allow(dead_code)]
 impl Error {
+    #[allow(dead_code)]
     pub(crate) fn new(line: usize, message: &'static str) -> Self {
-        Self::Syntax { line, message }
+        Self::Syntax {
+            file: None,
+            line,
+            message,
+        }
+    }
+
+    /// Shows the error followed by the line of `source` it points at, with a
+    /// caret under the column when the error knows it.
+    ///
+    /// ```text
+    /// [line 1] Error at ';': Expected ')' after expression.
+    /// 1 | print (3;
+    ///   |         ^
+    /// ```
+    pub fn with_context<'a>(&'a self, source: &'a str) -> ErrorWithContext<'a> {
+        ErrorWithContext {
+            error: self,
+            source,
+        }
+    }
+
+    /// The 1-based line and, if known, column the error was reported at.
+    fn location(&self) -> Option<(usize, Option<usize>)> {
+        match self {
+            Self::Syntax { line, .. }
+            | Self::UnexpectedEof { line, .. }
+            | Self::TypeError { line, .. }
+            | Self::UndefinedVariable { line, .. }
+            | Self::Arity { line, .. }
+            | Self::Import { line, .. }
+            | Self::NativeError { line, .. }
+            | Self::ResourceLimit { line, .. }
+            | Self::StackOverflow { line, .. } => Some((*line, None)),
+            Self::UnexpectedToken { line, column, .. } | Self::Scan { line, column, .. } => {
+                Some((*line, Some(*column)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the error came from input ending too soon, so that reading
+    /// more of it might fix it. The REPL uses this to ask for another line.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, Self::UnexpectedEof { .. })
+    }
+
+    /// Wraps an error from Rust code, such as a failure inside a native
+    /// function, keeping it as the [`source`](std::error::Error::source).
+    pub fn chain(
+        line: usize,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync + 'static>>,
+    ) -> Self {
+        Self::NativeError {
+            file: None,
+            line,
+            source: source.into(),
+        }
+    }
+
+    /// The name of the source the error was found in, if it has one.
+    pub fn source_name(&self) -> Option<&Path> {
+        match self {
+            Self::Syntax { file, .. }
+            | Self::UnexpectedToken { file, .. }
+            | Self::UnexpectedEof { file, .. }
+            | Self::Scan { file, .. }
+            | Self::TypeError { file, .. }
+            | Self::UndefinedVariable { file, .. }
+            | Self::Arity { file, .. }
+            | Self::Import { file, .. }
+            | Self::NativeError { file, .. }
+            | Self::ResourceLimit { file, .. }
+            | Self::StackOverflow { file, .. } => file.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Names the source of an error with a line, unless it already names
+    /// one. Errors are raised with the line of a token but not its source,
+    /// which is filled in as they pass back through code that has it.
+    pub(crate) fn in_source(mut self, name: Option<&Path>) -> Self {
+        let (Self::Syntax { file, .. }
+        | Self::UnexpectedToken { file, .. }
+        | Self::UnexpectedEof { file, .. }
+        | Self::Scan { file, .. }
+        | Self::TypeError { file, .. }
+        | Self::UndefinedVariable { file, .. }
+        | Self::Arity { file, .. }
+        | Self::Import { file, .. }
+        | Self::NativeError { file, .. }
+        | Self::ResourceLimit { file, .. }
+        | Self::StackOverflow { file, .. }) = &mut self
+        else {
+            return self;
+        };
+        if file.is_none() {
+            *file = name.map(Path::to_path_buf);
+        }
+        self
+    }
+
+    /// Gives a runtime error raised without a line, at line 0, the line
+    /// `line`. Native functions do not know where they were called from, so
+    /// the call fills it in.
+    pub(crate) fn at_line(mut self, line: usize) -> Self {
+        if let Self::TypeError { line: at, .. }
+        | Self::UndefinedVariable { line: at, .. }
+        | Self::Arity { line: at, .. }
+        | Self::Import { line: at, .. }
+        | Self::NativeError { line: at, .. }
+        | Self::ResourceLimit { line: at, .. }
+        | Self::StackOverflow { line: at, .. } = &mut self
+        {
+            if *at == 0 {
+                *at = line;
+            }
+        }
+        self
+    }
+
+    /// Which stage of running a program raised the error, for choosing how
+    /// to report it without matching on every variant.
+    pub fn phase(&self) -> Phase {
+        match self {
+            Self::IO(_) => Phase::IO,
+            Self::Scan { .. } | Self::LineRange { .. } => Phase::Scan,
+            Self::Syntax { .. } | Self::UnexpectedToken { .. } | Self::UnexpectedEof { .. } => {
+                Phase::Parse
+            }
+            Self::TypeError { .. }
+            | Self::UndefinedVariable { .. }
+            | Self::Arity { .. }
+            | Self::Import { .. }
+            | Self::NativeError { .. }
+            | Self::ResourceLimit { .. }
+            | Self::StackOverflow { .. }
+            | Self::Timeout
+            | Self::Interrupted
+            | Self::InvalidBytecode(_)
+            | Self::BytecodeVersion { .. } => Phase::Runtime,
+        }
+    }
+}
+
+/// An [`Error`] displayed along with the source line it points at, made by
+/// [`Error::with_context`].
+pub struct ErrorWithContext<'a> {
+    error: &'a Error,
+    source: &'a str,
+}
+
+impl std::fmt::Display for ErrorWithContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        let Some((line, column)) = self.error.location() else {
+            return Ok(());
+        };
+        let Some(text) = line
+            .checked_sub(1)
+            .and_then(|index| self.source.lines().nth(index))
+        else {
+            return Ok(());
+        };
+        let number = line.to_string();
+        write!(f, "\n{number} | {text}")?;
+        if let Some(column) = column {
+            // Keep tabs so the caret lines up however they are rendered.
+            let indent: String = text
+                .get(..column.saturating_sub(1))
+                .unwrap_or(text)
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, "\n{} | {indent}^", " ".repeat(number.len()))?;
+        }
+        Ok(())
     }
 }
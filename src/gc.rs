@@ -0,0 +1,193 @@
+//! Cycle collection for environments.
+//!
+//! Environments and the closures defined in them point at each other, so a
+//! scope that defined a function keeps itself alive through `Rc` after the
+//! program can no longer reach it. [`Heap::collect`] finds such scopes and
+//! empties them, which breaks the cycle and lets `Rc` free them.
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::callable::Callable;
+use crate::environment::Environment;
+use crate::interpret::{MemStats, Value};
+
+type Scope = Rc<RefCell<Environment>>;
+
+/// Every environment the interpreter has created that may still be alive.
+#[derive(Debug, Default)]
+pub(crate) struct Heap {
+    environments: Vec<Weak<RefCell<Environment>>>,
+    usage: Rc<MemCounter>,
+}
+
+/// The [`MemStats`] of tracked environments, which they update as their
+/// variables are defined, assigned and dropped.
+#[derive(Debug, Default)]
+pub(crate) struct MemCounter {
+    stats: Cell<MemStats>,
+    /// How many variables hold each bytes value, so that shared bytes are
+    /// counted once. Bytes never change length, so their size when first
+    /// counted is their size when last dropped.
+    bytes_holders: RefCell<HashMap<*const RefCell<Vec<u8>>, usize>>,
+}
+
+impl MemCounter {
+    /// Counts `value` as held by one more variable.
+    pub(crate) fn add(&self, value: &Value) {
+        match value {
+            Value::String(s) => self.update(|stats| stats.string_bytes += s.len()),
+            Value::Bytes(bytes) => {
+                let mut holders = self.bytes_holders.borrow_mut();
+                let count = holders.entry(Rc::as_ptr(bytes)).or_insert(0);
+                if *count == 0 {
+                    self.update(|stats| stats.binary_bytes += bytes.borrow().len());
+                }
+                *count += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// Counts `value` as held by one variable fewer.
+    pub(crate) fn remove(&self, value: &Value) {
+        match value {
+            Value::String(s) => self.update(|stats| stats.string_bytes -= s.len()),
+            Value::Bytes(bytes) => {
+                let mut holders = self.bytes_holders.borrow_mut();
+                let ptr = Rc::as_ptr(bytes);
+                let count = holders.get_mut(&ptr).expect("bytes were counted");
+                *count -= 1;
+                if *count == 0 {
+                    holders.remove(&ptr);
+                    self.update(|stats| stats.binary_bytes -= bytes.borrow().len());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Counts a tracked environment as freed.
+    pub(crate) fn free(&self) {
+        self.update(|stats| stats.heap_objects -= 1);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut MemStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+impl Heap {
+    /// Moves `environment` onto the heap so that it can be collected, and
+    /// counts its variables from then on.
+    pub(crate) fn track(&mut self, mut environment: Environment) -> Scope {
+        self.usage.update(|stats| stats.heap_objects += 1);
+        environment.count_usage(Rc::clone(&self.usage));
+        let environment = Rc::new(RefCell::new(environment));
+        self.environments.push(Rc::downgrade(&environment));
+        environment
+    }
+
+    /// How many tracked environments are alive.
+    pub(crate) fn len(&self) -> usize {
+        self.environments
+            .iter()
+            .filter(|environment| environment.strong_count() > 0)
+            .count()
+    }
+
+    /// The strings and bytes held by variables in live environments.
+    pub(crate) fn mem_usage(&self) -> MemStats {
+        self.usage.stats.get()
+    }
+
+    /// Empties every environment that is kept alive only by cycles.
+    ///
+    /// This is trial deletion: references between tracked environments,
+    /// through `enclosing` or through closures stored in their variables,
+    /// are counted, and any `Rc` count beyond those must come from outside,
+    /// such as the interpreter itself, the Rust stack or a value held by
+    /// the embedder. Environments reachable from those roots are kept.
+    pub(crate) fn collect(&mut self) {
+        self.environments
+            .retain(|environment| environment.strong_count() > 0);
+        let scopes: Vec<Scope> = self.environments.iter().filter_map(Weak::upgrade).collect();
+        let index: HashMap<*const RefCell<Environment>, usize> = scopes
+            .iter()
+            .enumerate()
+            .map(|(i, scope)| (Rc::as_ptr(scope), i))
+            .collect();
+
+        let mut internal = vec![0; scopes.len()];
+        let mut edges = vec![vec![]; scopes.len()];
+        let mut roots = vec![];
+        // Each closure found in a variable, with how many variables hold it.
+        let mut functions: HashMap<*const (), (Rc<dyn Callable>, usize)> = HashMap::new();
+        for (i, scope) in scopes.iter().enumerate() {
+            let Ok(environment) = scope.try_borrow() else {
+                roots.push(i);
+                continue;
+            };
+            if let Some(&j) = environment
+                .enclosing()
+                .and_then(|enclosing| index.get(&Rc::as_ptr(enclosing)))
+            {
+                internal[j] += 1;
+                edges[i].push(j);
+            }
+            for value in environment.values().values() {
+                let Value::Callable(function) = value else {
+                    continue;
+                };
+                let Some(&j) = function
+                    .closure()
+                    .and_then(|closure| index.get(&Rc::as_ptr(closure)))
+                else {
+                    continue;
+                };
+                edges[i].push(j);
+                functions
+                    .entry(Rc::as_ptr(function).cast())
+                    .or_insert_with(|| (Rc::clone(function), 0))
+                    .1 += 1;
+            }
+        }
+
+        for (function, holders) in functions.values() {
+            let closure = function.closure().expect("only closures are recorded");
+            let j = index[&Rc::as_ptr(closure)];
+            // One count is the clone held in `functions`.
+            if Rc::strong_count(function) - 1 > *holders {
+                roots.push(j);
+            } else {
+                internal[j] += 1;
+            }
+        }
+        for (i, scope) in scopes.iter().enumerate() {
+            // One count is the clone held in `scopes`.
+            if Rc::strong_count(scope) - 1 > internal[i] {
+                roots.push(i);
+            }
+        }
+
+        let mut reachable = vec![false; scopes.len()];
+        while let Some(i) = roots.pop() {
+            if !std::mem::replace(&mut reachable[i], true) {
+                roots.extend(&edges[i]);
+            }
+        }
+        drop(functions);
+        for (scope, reachable) in scopes.iter().zip(reachable) {
+            if !reachable {
+                if let Ok(mut environment) = scope.try_borrow_mut() {
+                    environment.clear();
+                }
+            }
+        }
+        drop(scopes);
+        self.environments
+            .retain(|environment| environment.strong_count() > 0);
+    }
+}
@@ -0,0 +1,59 @@
+//! The Lox grammar accepted by the [`Parser`](crate::syntax::Parser).
+//!
+//! Each rule below is implemented by the parser method of the same name
+//! (`varDecl` is `var_declaration`, `logic_or` is `or`, and so on), so the
+//! two can be read side by side. The notation is the book's: `*` repeats,
+//! `?` is optional, `|` separates alternatives, and quoted text and
+//! capitalized names are tokens.
+//!
+//! ```text
+//! program     → ( exportDecl | declaration )* EOF ;
+//! exportDecl  → "export" ( funDecl | varDecl | "default" expression ";" ) ;
+//!
+//! declaration → funDecl | importDecl | varDecl | statement ;
+//! funDecl     → "fun" IDENTIFIER "(" parameters? ")" block ;
+//! parameters  → IDENTIFIER ( "," IDENTIFIER )* ;
+//! importDecl  → "import" STRING ";" ;
+//! varDecl     → "var" IDENTIFIER ( "=" expression )? ";" ;
+//!
+//! statement   → exprStmt | forStmt | ifStmt | printStmt | returnStmt
+//!             | whileStmt | withStmt | block ;
+//! exprStmt    → expression ";" ;
+//! forStmt     → "for" "(" ( varDecl | exprStmt | ";" ) expression? ";"
+//!               expression? ")" statement ;
+//! ifStmt      → "if" "(" expression ")" statement ( "else" statement )? ;
+//! printStmt   → "print" expression ";" ;
+//! returnStmt  → "return" expression? ";" ;
+//! whileStmt   → "while" "(" expression ")" statement ;
+//! withStmt    → "with" "(" binding ( "," binding )* ")" statement ;
+//! binding     → IDENTIFIER "=" expression ;
+//! block       → "{" declaration* "}" ;
+//!
+//! expression  → assignment ;
+//! assignment  → IDENTIFIER "=" assignment | logic_or ;
+//! logic_or    → logic_and ( "or" logic_and )* ;
+//! logic_and   → equality ( "and" equality )* ;
+//! equality    → comparison ( ( "!=" | "==" ) comparison )* ;
+//! comparison  → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;
+//! term        → factor ( ( "-" | "+" ) factor )* ;
+//! factor      → cast ( ( "/" | "*" ) cast )* ;
+//! cast        → unary ( "as" IDENTIFIER )* ;
+//! unary       → ( "!" | "-" ) unary | call ;
+//! call        → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;
+//! arguments   → expression ( "," expression )* ;
+//! primary     → "true" | "false" | "nil" | NUMBER | STRING | IDENTIFIER
+//!             | "(" sequence ")" | "fun" "(" parameters? ")" block ;
+//! sequence    → expression ( "," expression )* ;
+//! ```
+//!
+//! `STRING` covers both `"..."` and `"""..."""` literals, except in
+//! `importDecl`, which takes only `"..."`. `sequence` is the comma operator,
+//! which the book leaves as an exercise, as are anonymous functions in
+//! `primary`. A `declaration` starting with `"fun" "("` is an `exprStmt`
+//! holding one, not a `funDecl`. `cast` is also an addition, where the
+//! `IDENTIFIER` is `number`, `string` or `bool`. `"default"` and `"as"` are
+//! `IDENTIFIER`s with that text; they are not reserved. `withStmt` is an
+//! addition too, and `"with"` is not reserved either: a statement is a
+//! `withStmt` only when it starts `with ( IDENTIFIER =` and the token after
+//! the matching `)` cannot continue an expression. It is otherwise read as
+//! an expression, such as a call of a function named `with`.
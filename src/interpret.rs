@@ -1,13 +1,74 @@
-use crate::error::{Error, Result};
-use crate::scanner::TokenType;
-use crate::syntax::Expr;
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::callable::{natives, since_epoch, Callable, LoxFunction, NativeFunction};
+use crate::environment::Environment;
+use crate::error::{Error, Result, Warning};
+use crate::gc::Heap;
+use crate::methods;
+use crate::random::RandomFunction;
+use crate::scanner::{Scanner, Token, TokenType};
+use crate::syntax::{Expr, Parser, Stmt};
+
+#[derive(Clone)]
+#[non_exhaustive]
 pub enum Value {
     String(String),
     Number(f64),
     Boolean(bool),
     Nil,
+    Callable(Rc<dyn Callable>),
+    #[cfg(feature = "regex")]
+    RegExp(Rc<regex::Regex>),
+    /// Raw binary data. Copies of the value share the bytes, so that
+    /// `bytes_set` changes them for every holder.
+    Bytes(Rc<RefCell<Vec<u8>>>),
+}
+
+/// Callables are equal only to themselves, regular expressions to those
+/// with the same pattern, and bytes to those with the same contents.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::Boolean(l), Value::Boolean(r)) => l == r,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Callable(l), Value::Callable(r)) => Rc::ptr_eq(l, r),
+            #[cfg(feature = "regex")]
+            (Value::RegExp(l), Value::RegExp(r)) => l.as_str() == r.as_str(),
+            (Value::Bytes(l), Value::Bytes(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+/// Values of the same type are ordered: numbers by value, strings and
+/// bytes by their bytes, and `false` before `true`. Values of different
+/// types, and distinct callables, are unordered.
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Value::String(l), Value::String(r)) => l.partial_cmp(r),
+            (Value::Number(l), Value::Number(r)) => l.partial_cmp(r),
+            (Value::Boolean(l), Value::Boolean(r)) => l.partial_cmp(r),
+            (Value::Bytes(l), Value::Bytes(r)) => l.partial_cmp(r),
+            (Value::Nil, Value::Nil) => Some(std::cmp::Ordering::Equal),
+            (Value::Callable(_), Value::Callable(_)) if self == other => {
+                Some(std::cmp::Ordering::Equal)
+            }
+            #[cfg(feature = "regex")]
+            (Value::RegExp(_), Value::RegExp(_)) if self == other => {
+                Some(std::cmp::Ordering::Equal)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -17,10 +78,22 @@ impl std::fmt::Display for Value {
             Value::Number(n) => write!(f, "{n}"),
             Value::Boolean(b) => write!(f, "{b}"),
             Value::Nil => write!(f, "nil"),
+            Value::Callable(c) => write!(f, "{c}"),
+            #[cfg(feature = "regex")]
+            Value::RegExp(re) => write!(f, "/{re}/"),
+            Value::Bytes(bytes) => write!(f, "<{} bytes>", bytes.borrow().len()),
         }
     }
 }
 
+/// The value as Lox prints it, followed by its type, such as `"hello"
+/// (string)` or `42 (number)`, so that test failures read like Lox.
+impl std::fmt::Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self} ({})", self.kind())
+    }
+}
+
 impl From<f64> for Value {
     fn from(value: f64) -> Self {
         Value::Number(value)
@@ -39,6 +112,40 @@ impl From<String> for Value {
     }
 }
 
+#[cfg(feature = "serde_json")]
+impl From<Value> for serde_json::Value {
+    /// Numbers that JSON cannot represent (NaN and the infinities) and
+    /// functions become `null`. Regular expressions become their pattern,
+    /// and bytes an array of numbers.
+    fn from(value: Value) -> Self {
+        match value {
+            Value::String(s) => s.into(),
+            Value::Number(n) => serde_json::Number::from_f64(n).map_or(Self::Null, Self::Number),
+            Value::Boolean(b) => b.into(),
+            Value::Nil | Value::Callable(_) => Self::Null,
+            #[cfg(feature = "regex")]
+            Value::RegExp(re) => re.as_str().into(),
+            Value::Bytes(bytes) => bytes.borrow().as_slice().into(),
+        }
+    }
+}
+
+#[cfg(feature = "serde_json")]
+impl TryFrom<serde_json::Value> for Value {
+    /// Arrays and objects have no Lox equivalent yet, and are handed back.
+    type Error = serde_json::Value;
+
+    fn try_from(value: serde_json::Value) -> std::result::Result<Self, Self::Error> {
+        match value {
+            serde_json::Value::Null => Ok(Value::Nil),
+            serde_json::Value::Bool(b) => Ok(b.into()),
+            serde_json::Value::Number(n) => n.as_f64().map(Value::from).ok_or(n.into()),
+            serde_json::Value::String(s) => Ok(s.into()),
+            value => Err(value),
+        }
+    }
+}
+
 impl std::ops::Not for Value {
     type Output = Self;
 
@@ -47,121 +154,1353 @@ impl std::ops::Not for Value {
     }
 }
 
+/// Arithmetic with Lox's rules, for values built in Rust. Type errors
+/// report line 0, since the operands come from no source line. Unlike the
+/// interpreter, `+` does not limit the length of the strings it builds.
+impl std::ops::Add for Value {
+    type Output = Result<Value>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Value::Number(l), Value::Number(r)) => Ok((l + r).into()),
+            (Value::String(l), Value::String(r)) => Ok((l + &r).into()),
+            (l, r) => Err(Value::add_type_error(0, &l, &r)),
+        }
+    }
+}
+
+impl std::ops::Sub for Value {
+    type Output = Result<Value>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Ok((self.into_double(0)? - rhs.into_double(0)?).into())
+    }
+}
+
+impl std::ops::Mul for Value {
+    type Output = Result<Value>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Ok((self.into_double(0)? * rhs.into_double(0)?).into())
+    }
+}
+
+impl std::ops::Div for Value {
+    type Output = Result<Value>;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Ok((self.into_double(0)? / rhs.into_double(0)?).into())
+    }
+}
+
+impl std::ops::Neg for Value {
+    type Output = Result<Value>;
+
+    fn neg(self) -> Self::Output {
+        Ok((-self.into_double(0)?).into())
+    }
+}
+
 impl Value {
-    fn into_double(self, line: usize) -> Result<f64> {
-        if let Self::Number(num) = self {
-            Ok(num)
-        } else {
-            Err(Error::TypeError {
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Only booleans give `Some`; `nil` is falsey but gives `None`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The value as text, like [`Display`](std::fmt::Display) but with
+    /// strings unquoted, so `"hi"` gives `hi` and `42` gives `42`.
+    pub fn to_lox_string(&self) -> String {
+        match self {
+            Value::String(s) => s.clone(),
+            value => value.to_string(),
+        }
+    }
+
+    /// A copy that shares nothing mutable with the original: bytes get a
+    /// buffer of their own. Every other value is returned as it is, since
+    /// strings, numbers, booleans and nil are copied anyway, and functions
+    /// and regular expressions cannot be changed.
+    pub fn deep_clone(&self) -> Value {
+        match self {
+            Value::Bytes(bytes) => Value::Bytes(Rc::new(RefCell::new(bytes.borrow().clone()))),
+            value => value.clone(),
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    pub(crate) fn into_double(self, line: usize) -> Result<f64> {
+        match self {
+            Self::Number(num) => Ok(num),
+            value => Err(Error::TypeError {
+                file: None,
                 line,
-                message: "Expected number",
-            })
+                message: format!("Expected number but got {}.", value.kind()),
+            }),
+        }
+    }
+
+    /// The error for `left + right` when the operands are neither two
+    /// numbers nor two strings. Forgetting to initialize a variable is the
+    /// usual mistake, so `nil` is called out.
+    pub(crate) fn add_type_error(line: usize, left: &Value, right: &Value) -> Error {
+        let message = match (left, right) {
+            (Value::Nil, other) | (other, Value::Nil) => {
+                format!("Cannot add nil to {}.", other.kind())
+            }
+            _ => format!(
+                "Expected two numbers or two strings but got {} and {}.",
+                left.kind(),
+                right.kind()
+            ),
+        };
+        Error::TypeError {
+            file: None,
+            line,
+            message,
+        }
+    }
+
+    /// Converts the value for `expr as type`. Any value becomes a string by
+    /// [`to_lox_string`](Value::to_lox_string), so a string's contents are
+    /// kept without quotes, or a boolean by its truthiness, but only numbers
+    /// and strings holding one become numbers.
+    fn cast(self, target_type: &Token) -> Result<Value> {
+        match (target_type.lexeme.as_str(), self) {
+            ("number", Value::Number(n)) => Ok(n.into()),
+            ("number", Value::String(s)) => {
+                s.trim()
+                    .parse::<f64>()
+                    .map(Value::from)
+                    .map_err(|_| Error::TypeError {
+                        file: target_type.file(),
+                        line: target_type.line,
+                        message: format!("Cannot convert string \"{s}\" to number."),
+                    })
+            }
+            ("number", value) => Err(Error::TypeError {
+                file: target_type.file(),
+                line: target_type.line,
+                message: format!("Cannot convert {} to number.", value.kind()),
+            }),
+            ("string", value) => Ok(value.to_lox_string().into()),
+            ("bool", value) => Ok(value.is_truthy().into()),
+            _ => unreachable!("the parser only accepts known type names"),
         }
     }
 
     fn is_truthy(&self) -> bool {
         match self {
-            Value::String(_) | Value::Number(_) => true,
+            Value::String(_) | Value::Number(_) | Value::Callable(_) => true,
+            #[cfg(feature = "regex")]
+            Value::RegExp(_) => true,
+            Value::Bytes(_) => true,
             Value::Boolean(b) => *b,
             Value::Nil => false,
         }
     }
 
-    #[allow(dead_code)]
-    fn kind(&self) -> &'static str {
+    pub(crate) fn kind(&self) -> &'static str {
         match self {
             Value::String(_) => "string",
             Value::Number(_) => "number",
             Value::Boolean(_) => "boolean",
             Value::Nil => "nil",
+            Value::Callable(_) => "function",
+            #[cfg(feature = "regex")]
+            Value::RegExp(_) => "regexp",
+            Value::Bytes(_) => "bytes",
+        }
+    }
+
+    /// Wraps the value so it can be used as a `HashMap` key. Functions and
+    /// bytes cannot be keys; the error is at `line`, where the key is used.
+    pub fn to_hashable(&self, line: usize) -> Result<HashableValue> {
+        match self {
+            Value::String(_) | Value::Number(_) | Value::Boolean(_) | Value::Nil => {
+                Ok(HashableValue(self.clone()))
+            }
+            #[cfg(feature = "regex")]
+            Value::RegExp(_) => Ok(HashableValue(self.clone())),
+            Value::Callable(_) => Err(Error::TypeError {
+                file: None,
+                line,
+                message: "Functions cannot be used as a key.".to_string(),
+            }),
+            Value::Bytes(_) => Err(Error::TypeError {
+                file: None,
+                line,
+                message: "Bytes cannot be used as a key.".to_string(),
+            }),
+        }
+    }
+}
+
+/// A [`Value`] with total equality and a hash, for use as a map key.
+///
+/// Numbers are compared by their bits after canonicalizing them: every NaN
+/// is the same key, as are `0` and `-0`.
+#[derive(Debug, Clone)]
+pub struct HashableValue(Value);
+
+impl HashableValue {
+    pub fn value(&self) -> &Value {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+fn canonical_bits(num: f64) -> u64 {
+    if num.is_nan() {
+        f64::NAN.to_bits()
+    } else if num == 0.0 {
+        0
+    } else {
+        num.to_bits()
+    }
+}
+
+impl PartialEq for HashableValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Value::Number(l), Value::Number(r)) => canonical_bits(*l) == canonical_bits(*r),
+            (l, r) => l == r,
         }
     }
 }
 
-pub fn interpret(expr: Expr) {
-    match evaluate(expr) {
-        Ok(v) => println!("{v}"),
-        Err(e) => eprintln!("{e}"),
+impl Eq for HashableValue {}
+
+impl std::hash::Hash for HashableValue {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(&self.0).hash(state);
+        match &self.0 {
+            Value::String(s) => s.hash(state),
+            Value::Number(n) => canonical_bits(*n).hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Nil => {}
+            #[cfg(feature = "regex")]
+            Value::RegExp(re) => re.as_str().hash(state),
+            Value::Callable(_) | Value::Bytes(_) => {
+                unreachable!("functions and bytes are never hashable")
+            }
+        }
     }
 }
 
-pub fn evaluate(expr: Expr) -> Result<Value> {
-    match expr {
-        Expr::Binary {
-            left,
-            operator,
-            right,
-        } => {
-            let left = evaluate(*left)?;
-            let right = evaluate(*right)?;
-            match operator.ty {
-                TokenType::Minus => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left - right).into())
+/// A tree-walk interpreter that keeps its global variables between runs.
+pub struct Interpreter {
+    /// Native functions, enclosing the globals.
+    builtins: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    max_string_length: usize,
+    max_call_depth: usize,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    steps: usize,
+    /// Where `print` writes while a [`PrintCapture`] is alive, with the
+    /// token of the capture that owns it, or `None` for stdout.
+    print_capture: CaptureBuffer,
+    /// Whether conditions and `!` require booleans.
+    strict: bool,
+    /// Whether each statement is written to stderr before it runs.
+    trace: bool,
+    /// How many times a loop condition has been evaluated.
+    iterations: u64,
+    /// How many functions, native or not, have been called.
+    function_calls: u64,
+    /// Set from another thread to stop the program that is running.
+    interrupt: Arc<AtomicBool>,
+    /// The files being run, the script first and then each module whose
+    /// import is in progress, to resolve relative imports and catch cycles.
+    loading: Vec<PathBuf>,
+    /// The value of the last `export default` run by the current module.
+    default_export: Option<Value>,
+    /// Whether [`run_sandboxed`](Self::run_sandboxed) is running, which
+    /// rules out `import`.
+    sandboxed: bool,
+    /// Warnings from runs that are not given a list to push them to, until
+    /// [`take_warnings`](Self::take_warnings).
+    warnings: Vec<Warning>,
+    heap: Heap,
+    call_stack: Vec<CallFrame>,
+    /// The function whose body is running, which a call in tail position
+    /// may reuse the frame of.
+    current_function: Option<Rc<dyn Callable>>,
+    /// The arguments of a tail call waiting for [`call_at`](Self::call_at)
+    /// to run it, and whether its value is dropped rather than returned.
+    tail_call: Option<(Vec<Value>, bool)>,
+}
+
+/// What a program's variables hold, from [`Interpreter::mem_usage`].
+///
+/// The counts are kept up to date as variables are defined, assigned and
+/// dropped with their scope. Values held only by an expression being
+/// evaluated are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemStats {
+    /// The bytes of text in strings stored in variables.
+    pub string_bytes: usize,
+    /// The contents of bytes values stored in variables, counting bytes
+    /// shared by several variables once.
+    pub binary_bytes: usize,
+    /// The elements of arrays stored in variables. Lox has no arrays yet,
+    /// so this is always 0.
+    pub array_elements: usize,
+    /// The entries of maps stored in variables. Lox has no maps yet, so
+    /// this is always 0.
+    pub map_entries: usize,
+    /// The scopes that are alive, as [`Interpreter::heap_usage`] counts.
+    pub heap_objects: usize,
+}
+
+/// A function call in progress, from [`Interpreter::call_stack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallFrame {
+    /// The called function's name, or `<anonymous>` for a `fun` expression.
+    pub function_name: String,
+    /// The source the call is in, when it has a name, such as the script
+    /// or module file.
+    pub file: Option<PathBuf>,
+    /// The line of the call, or 0 for a call made from Rust.
+    pub line: usize,
+}
+
+/// Shows the frame as a line of a stack trace: `at f (script.lox:3)`, or
+/// `at f (line 3)` for a source without a name.
+impl std::fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(
+                f,
+                "at {} ({}:{})",
+                self.function_name,
+                file.display(),
+                self.line
+            ),
+            None => write!(f, "at {} (line {})", self.function_name, self.line),
+        }
+    }
+}
+
+/// The output of the running [`PrintCapture`], with that capture's token.
+type CaptureBuffer = Rc<RefCell<Option<(Rc<()>, Vec<u8>)>>>;
+
+/// Collects what `print` writes while it is alive, made by
+/// [`Interpreter::capture_print`]. Dropping it sends output back to stdout.
+pub struct PrintCapture {
+    buffer: CaptureBuffer,
+    /// Tells this capture's buffer apart from one a later capture started.
+    token: Rc<()>,
+}
+
+impl PrintCapture {
+    /// Everything printed since the capture began, or nothing once a later
+    /// capture has taken over.
+    pub fn output(&self) -> String {
+        match &*self.buffer.borrow() {
+            Some((token, output)) if Rc::ptr_eq(token, &self.token) => {
+                String::from_utf8_lossy(output).into_owned()
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+impl Drop for PrintCapture {
+    /// Sends output back to stdout, unless a later capture is still running.
+    fn drop(&mut self) {
+        let mut buffer = self.buffer.borrow_mut();
+        if matches!(&*buffer, Some((token, _)) if Rc::ptr_eq(token, &self.token)) {
+            *buffer = None;
+        }
+    }
+}
+
+/// Helper functions written in Lox, for
+/// [`load_prelude`](Interpreter::load_prelude): `abs`, `min`, `max`, `clamp`
+/// and `pow`.
+pub const STDLIB: &str = include_str!("stdlib.lox");
+
+/// How many statements run between checks of the clock.
+const STEPS_PER_DEADLINE_CHECK: usize = 1000;
+
+/// How many statements run between checks of the interrupt signal.
+const STEPS_PER_INTERRUPT_CHECK: usize = 100;
+
+/// How many statements run between automatic garbage collections.
+const STEPS_PER_GC: usize = 1000;
+
+/// How many calls can be in progress at once before
+/// [`Error::StackOverflow`], by default: deep enough for ordinary recursion,
+/// and shallow enough to stop a runaway one quickly.
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// How much Rust stack must be left when a call starts. Less than this, and
+/// the call runs on a new [`STACK_SEGMENT`] allocated on the heap, so that
+/// no depth of Lox calls can overflow the thread's stack.
+const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// The size of each extra piece of stack that deep calls run on.
+const STACK_SEGMENT: usize = 2 * 1024 * 1024;
+
+impl Default for Interpreter {
+    /// Native functions live in a scope enclosing the globals, so they are
+    /// visible everywhere but not listed among the user's variables.
+    fn default() -> Self {
+        let mut builtins = Environment::new();
+        for native in natives() {
+            builtins.define(native.name(), Value::Callable(Rc::new(native)));
+        }
+        let seed = since_epoch().as_nanos() as u64;
+        for (name, native) in RandomFunction::natives(seed) {
+            builtins.define(name, Value::Callable(Rc::new(native)));
+        }
+        let builtins = Rc::new(RefCell::new(builtins));
+        let mut heap = Heap::default();
+        let globals = heap.track(Environment::new_enclosed(Rc::clone(&builtins)));
+        Self {
+            builtins,
+            environment: Rc::clone(&globals),
+            globals,
+            max_string_length: usize::MAX,
+            max_call_depth: MAX_CALL_DEPTH,
+            timeout: None,
+            deadline: None,
+            steps: 0,
+            print_capture: Rc::default(),
+            strict: false,
+            trace: false,
+            iterations: 0,
+            function_calls: 0,
+            interrupt: Arc::default(),
+            loading: vec![],
+            default_export: None,
+            sandboxed: false,
+            warnings: vec![],
+            heap,
+            call_stack: Vec::new(),
+            current_function: None,
+            tail_call: None,
+        }
+    }
+}
+
+impl Interpreter {
+    /// The standard configuration, same as [`Interpreter::default`]: native
+    /// functions such as `clock` are defined, `print` writes to stdout,
+    /// strings have no length limit, and there is no timeout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `statements`, reporting the first runtime error to stderr.
+    pub fn interpret(&mut self, statements: &[Stmt], warnings: &mut Vec<Warning>) {
+        if let Err(e) = self.execute_all(statements, warnings) {
+            eprintln!("{e}");
+        }
+    }
+
+    /// Limits strings built by concatenation, casts, methods and native
+    /// functions to `max` bytes, so a runaway program fails with
+    /// [`Error::ResourceLimit`] instead of exhausting memory. Unlimited by
+    /// default.
+    pub fn with_max_string_length(mut self, max: usize) -> Self {
+        self.max_string_length = max;
+        self
+    }
+
+    /// Fails calls nested more than `max` deep with
+    /// [`Error::StackOverflow`]. The default is 1000.
+    ///
+    /// Any `max` is safe: calls move onto stack allocated on the heap when
+    /// the thread's own runs low, so deep recursion cannot overflow the Rust
+    /// stack, and it is memory that bounds a raised limit instead. Each
+    /// level of nesting takes some tens of kilobytes in a debug build and
+    /// a few in a release build.
+    pub fn with_max_call_depth(mut self, max: usize) -> Self {
+        self.max_call_depth = max;
+        self
+    }
+
+    /// Stops each call to [`execute_all`](Self::execute_all) with
+    /// [`Error::Timeout`] once it has run for longer than `timeout`. The
+    /// clock is checked every thousand statements, so a run can overshoot
+    /// slightly.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Seeds the generator behind the `random` and `random_int` natives, so
+    /// that they give the same numbers on every run. Without a seed, it is
+    /// seeded from the clock.
+    pub fn with_random_seed(self, seed: u64) -> Self {
+        for (name, native) in RandomFunction::natives(seed) {
+            self.builtins
+                .borrow_mut()
+                .define(name, Value::Callable(Rc::new(native)));
+        }
+        self
+    }
+
+    /// Resolves `import` statements in the script relative to the directory
+    /// containing `path`, rather than the current directory.
+    pub fn with_script_path(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        self.loading = vec![path.canonicalize().unwrap_or(path)];
+        self
+    }
+
+    /// Sends the output of `print` to a buffer instead of stdout until the
+    /// returned guard is dropped, so tests can check what a script printed.
+    /// Starting a new capture discards anything an earlier one still holds,
+    /// and dropping the earlier one then leaves the new one running.
+    ///
+    /// ```
+    /// let mut interpreter = lox::interpret::Interpreter::new();
+    /// let capture = interpreter.capture_print();
+    /// let statements = lox::parse("print 1 + 2;").unwrap();
+    /// interpreter.execute_all(&statements, &mut vec![]).unwrap();
+    /// assert_eq!(capture.output(), "3\n");
+    /// ```
+    pub fn capture_print(&self) -> PrintCapture {
+        let token = Rc::new(());
+        *self.print_capture.borrow_mut() = Some((Rc::clone(&token), vec![]));
+        PrintCapture {
+            buffer: Rc::clone(&self.print_capture),
+            token,
+        }
+    }
+
+    /// Makes it a [`Error::TypeError`] for the condition of an `if`, `while`
+    /// or `for`, or the operand of `!`, to be anything but a boolean, rather
+    /// than using its truthiness.
+    pub fn enable_strict_mode(&mut self) {
+        self.strict = true;
+    }
+
+    /// Writes each statement's line and kind to stderr before running it,
+    /// such as `[line 3] print`. Blocks and `export` are not written, only
+    /// the statements inside them.
+    pub fn enable_tracing(&mut self) {
+        self.trace = true;
+    }
+
+    /// How many loop iterations have run since the interpreter was created
+    /// or [`reset_stats`](Self::reset_stats) was called. This counts every
+    /// evaluation of a `while` or `for` condition, including the last one
+    /// that ends the loop.
+    pub fn iteration_count(&self) -> u64 {
+        self.iterations
+    }
+
+    /// How many calls, to Lox or native functions, have been made since the
+    /// interpreter was created or [`reset_stats`](Self::reset_stats) was
+    /// called.
+    pub fn function_call_count(&self) -> u64 {
+        self.function_calls
+    }
+
+    /// Zeroes [`iteration_count`](Self::iteration_count) and
+    /// [`function_call_count`](Self::function_call_count).
+    pub fn reset_stats(&mut self) {
+        self.iterations = 0;
+        self.function_calls = 0;
+    }
+
+    /// A flag that stops the running program with [`Error::Interrupted`]
+    /// when set, so that another thread can cancel a script without killing
+    /// the process. It is checked every hundred statements, and cleared once
+    /// the program has stopped so the interpreter can be used again.
+    pub fn interrupt_signal(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupt)
+    }
+
+    /// Makes `native` callable from Lox under its own name, replacing any
+    /// native already defined with that name. Globals still take precedence.
+    pub fn define_native(&mut self, native: NativeFunction) {
+        self.builtins
+            .borrow_mut()
+            .define(native.name(), Value::Callable(Rc::new(native)));
+    }
+
+    /// Frees scopes that are no longer reachable but are kept alive by
+    /// reference cycles, such as a function call's scope holding a closure
+    /// that captured it. This also runs every thousand statements.
+    pub fn gc(&mut self) {
+        self.heap.collect();
+    }
+
+    /// Roughly how many objects are alive on the heap: the number of scopes
+    /// that have not been freed.
+    pub fn heap_usage(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// The memory held by the variables of live scopes. The counts are
+    /// kept as the program runs, so reading them is cheap.
+    pub fn mem_usage(&self) -> MemStats {
+        self.heap.mem_usage()
+    }
+
+    /// The function calls in progress, outermost first. After a runtime
+    /// error it holds the calls the error happened in, until the next run.
+    pub fn call_stack(&self) -> &[CallFrame] {
+        &self.call_stack
+    }
+
+    /// Removes and returns the warnings raised since the last call by
+    /// [`load_prelude`](Self::load_prelude),
+    /// [`run_sandboxed`](Self::run_sandboxed) and [`call`](Self::call),
+    /// which have no list of warnings passed in.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Runs `f` with the pending warnings as the list to push to, keeping
+    /// any that a nested run added meanwhile.
+    fn with_pending_warnings<T>(&mut self, f: impl FnOnce(&mut Self, &mut Vec<Warning>) -> T) -> T {
+        let mut warnings = std::mem::take(&mut self.warnings);
+        let result = f(self, &mut warnings);
+        warnings.append(&mut self.warnings);
+        self.warnings = warnings;
+        result
+    }
+
+    /// Evaluates `expr` as a run of its own, within the timeout, as
+    /// [`Lox::eval`](crate::Lox::eval) and the REPL do for a lone
+    /// expression.
+    pub(crate) fn evaluate_run(
+        &mut self,
+        expr: &Expr,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.call_stack.clear();
+        let result = self.evaluate(expr, warnings);
+        self.deadline = None;
+        result
+    }
+
+    /// Forgets every variable defined so far, keeping any limits and natives.
+    pub fn reset(&mut self) {
+        self.globals = self
+            .heap
+            .track(Environment::new_enclosed(Rc::clone(&self.builtins)));
+        self.environment = Rc::clone(&self.globals);
+    }
+
+    /// Copies every global variable out of the interpreter.
+    pub fn export_globals(&self) -> HashMap<String, Value> {
+        self.globals.borrow().values().clone()
+    }
+
+    /// Defines each entry of `globals` as a global variable, replacing any
+    /// existing variable with the same name.
+    pub fn import_globals(&mut self, globals: HashMap<String, Value>) {
+        let mut environment = self.globals.borrow_mut();
+        for (name, value) in globals {
+            environment.define(name, value);
+        }
+    }
+
+    pub(crate) fn globals(&self) -> Ref<'_, Environment> {
+        self.globals.borrow()
+    }
+
+    /// Runs `statements` in order, stopping at the first runtime error.
+    /// A `return` outside of any function stops execution early.
+    ///
+    /// The statements are only borrowed, so a program parsed once with
+    /// [`parse`](crate::parse) can be run any number of times:
+    ///
+    /// ```
+    /// use lox::interpret::{Interpreter, Value};
+    ///
+    /// let program = lox::parse("count = count + 1;").unwrap();
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.import_globals([("count".to_string(), Value::Number(0.0))].into());
+    /// for _ in 0..3 {
+    ///     interpreter.execute_all(&program, &mut vec![]).unwrap();
+    /// }
+    /// assert_eq!(interpreter.export_globals()["count"], Value::Number(3.0));
+    /// ```
+    pub fn execute_all(&mut self, statements: &[Stmt], warnings: &mut Vec<Warning>) -> Result<()> {
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.call_stack.clear();
+        let result = self.execute_statements(statements, warnings);
+        // Expressions evaluated between runs must not trip an old deadline.
+        self.deadline = None;
+        result?;
+        Ok(())
+    }
+
+    /// Runs `source` in the global scope, keeping the variables already
+    /// defined, so that what it defines is available to every later run.
+    /// Pass [`STDLIB`] to define the standard library.
+    pub fn load_prelude(&mut self, source: &str) -> Result<()> {
+        let statements = crate::parse(source)?;
+        self.with_pending_warnings(|interpreter, warnings| {
+            interpreter.execute_all(&statements, warnings)
+        })
+    }
+
+    /// Runs `source` where only the globals named in `allowed_globals`
+    /// (including natives such as `clock`) are visible, producing the value
+    /// of a top-level `return`, or `nil`.
+    ///
+    /// The script gets its own scope on top of a snapshot of the allowed
+    /// globals, so nothing it defines or assigns leaks into this
+    /// interpreter's globals. It cannot `import`, since a module would read
+    /// the file system and see every native.
+    pub fn run_sandboxed(&mut self, source: &str, allowed_globals: &[&str]) -> Result<Value> {
+        let statements = crate::parse(source)?;
+        let mut visible = Environment::new();
+        for (name, value) in self.globals.borrow().iter() {
+            if allowed_globals.contains(&name.as_str()) {
+                visible.define(name, value);
+            }
+        }
+        let sandbox = Environment::new_with_frozen_globals(&visible.freeze());
+
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.call_stack.clear();
+        self.sandboxed = true;
+        let result = self.with_pending_warnings(|interpreter, warnings| {
+            interpreter.execute_block(&statements, sandbox, warnings)
+        });
+        self.sandboxed = false;
+        self.deadline = None;
+        Ok(result?.unwrap_or(Value::Nil))
+    }
+
+    /// Runs a function body in `environment`, producing its return value.
+    pub(crate) fn execute_body(
+        &mut self,
+        statements: &[Stmt],
+        environment: Environment,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        let environment = self.heap.track(environment);
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.execute_function_statements(statements, warnings);
+        self.environment = previous;
+        Ok(result?.unwrap_or(Value::Nil))
+    }
+
+    /// Runs the statements of a function body. A call that ends the body,
+    /// such as `count(n - 1);`, is in tail position although its value is
+    /// dropped, as is the value of any `return`.
+    fn execute_function_statements(
+        &mut self,
+        statements: &[Stmt],
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Option<Value>> {
+        let Some((Stmt::Expression(expr), rest)) = statements.split_last() else {
+            return self.execute_statements(statements, warnings);
+        };
+        if let Some(value) = self.execute_statements(rest, warnings)? {
+            return Ok(Some(value));
+        }
+        self.step()?;
+        self.evaluate_tail(expr, true, warnings)?;
+        Ok(None)
+    }
+
+    /// Runs `statements` until one of them returns, yielding the returned
+    /// value.
+    fn execute_statements(
+        &mut self,
+        statements: &[Stmt],
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Option<Value>> {
+        for statement in statements {
+            if let Some(value) = self.execute(statement, warnings)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    fn execute(&mut self, stmt: &Stmt, warnings: &mut Vec<Warning>) -> Result<Option<Value>> {
+        self.step()?;
+        if self.trace {
+            trace(stmt);
+        }
+        match stmt {
+            Stmt::Block(statements) => {
+                let environment = Environment::new_enclosed(Rc::clone(&self.environment));
+                self.execute_block(statements, environment, warnings)
+            }
+            Stmt::Export(declaration) => self.execute(declaration, warnings),
+            Stmt::ExportDefault(expr) => {
+                self.default_export = Some(self.evaluate(expr, warnings)?);
+                Ok(None)
+            }
+            Stmt::Expression(expr) => {
+                self.evaluate(expr, warnings)?;
+                Ok(None)
+            }
+            Stmt::Function(declaration) => {
+                let function =
+                    LoxFunction::new(Rc::clone(declaration), Rc::clone(&self.environment));
+                self.environment.borrow_mut().define(
+                    declaration.name.lexeme.clone(),
+                    Value::Callable(Rc::new(function)),
+                );
+                Ok(None)
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let value = self.evaluate(condition, warnings)?;
+                if self.truthy(value, condition.token())? {
+                    self.execute(then_branch, warnings)
+                } else if let Some(else_branch) = else_branch {
+                    self.execute(else_branch, warnings)
+                } else {
+                    Ok(None)
                 }
-                TokenType::Slash => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left / right).into())
+            }
+            Stmt::Import { path } => {
+                self.import(path, warnings)?;
+                Ok(None)
+            }
+            Stmt::Print(expr) => {
+                let value = self.evaluate(expr, warnings)?;
+                match self.print_capture.borrow_mut().as_mut() {
+                    Some((_, buffer)) => buffer.extend(format!("{value}\n").into_bytes()),
+                    None => println!("{value}"),
                 }
-                TokenType::Star => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left * right).into())
+                Ok(None)
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(expr) => self.evaluate_tail(expr, false, warnings)?,
+                    None => Value::Nil,
+                };
+                Ok(Some(value))
+            }
+            Stmt::Var { name, initializer } => {
+                let value = match initializer {
+                    Some(expr) => self.evaluate(expr, warnings)?,
+                    None => Value::Nil,
+                };
+                self.environment
+                    .borrow_mut()
+                    .define(name.lexeme.clone(), value);
+                Ok(None)
+            }
+            Stmt::While { condition, body } => loop {
+                self.iterations += 1;
+                let value = self.evaluate(condition, warnings)?;
+                if !self.truthy(value, condition.token())? {
+                    return Ok(None);
                 }
-                TokenType::Plus => match (left, right) {
-                    (Value::Number(l), Value::Number(r)) => Ok((l + r).into()),
-                    (Value::String(l), Value::String(r)) => Ok((l + &r).into()),
-                    _ => Err(Error::TypeError {
-                        line: operator.line,
-                        message: "Invalid operand types for '+'",
-                    }),
-                },
-                TokenType::Greater => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left > right).into())
+                if let Some(value) = self.execute(body, warnings)? {
+                    return Ok(Some(value));
                 }
-                TokenType::GreaterEqual => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left >= right).into())
+            },
+            Stmt::With { bindings, body } => {
+                let mut environment = Environment::new_enclosed_with_capacity(
+                    Rc::clone(&self.environment),
+                    bindings.len(),
+                );
+                let mut values = Vec::with_capacity(bindings.len());
+                for (_, initializer) in bindings {
+                    values.push(self.evaluate(initializer, warnings)?);
                 }
-                TokenType::Less => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left < right).into())
+                for ((name, _), value) in bindings.iter().zip(values) {
+                    environment.define(name.lexeme.clone(), value);
                 }
-                TokenType::LessEqual => {
-                    let left = left.into_double(operator.line)?;
-                    let right = right.into_double(operator.line)?;
-                    Ok((left <= right).into())
+                self.execute_block(std::slice::from_ref(body), environment, warnings)
+            }
+        }
+    }
+
+    /// Calls a Lox function, such as one read back with
+    /// [`export_globals`](Self::export_globals), from Rust. The call is
+    /// limited by [`with_timeout`](Self::with_timeout) as a run is, and the
+    /// warnings it raises wait for [`take_warnings`](Self::take_warnings).
+    ///
+    /// No line of Lox made the call, so its frame in
+    /// [`call_stack`](Self::call_stack) has line 0, as do errors raised
+    /// before the call starts, for a `callee` that is not callable or the
+    /// wrong number of `arguments`.
+    pub fn call(&mut self, callee: Value, arguments: Vec<Value>) -> Result<Value> {
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.call_stack.clear();
+        let result = self.with_pending_warnings(|interpreter, warnings| {
+            interpreter.call_at(None, callee, arguments, warnings)
+        });
+        self.deadline = None;
+        result
+    }
+
+    /// Calls `callee` from the closing parenthesis `paren` of a call, or
+    /// from Rust when there is none.
+    fn call_at(
+        &mut self,
+        paren: Option<&Token>,
+        callee: Value,
+        arguments: Vec<Value>,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        let line = paren.map_or(0, |paren| paren.line);
+        let Value::Callable(function) = callee else {
+            return Err(Error::TypeError {
+                file: None,
+                line,
+                message: format!(
+                    "Can only call functions and classes, not {}.",
+                    callee.kind()
+                ),
+            });
+        };
+        if arguments.len() != function.arity() {
+            return Err(Error::Arity {
+                file: None,
+                line,
+                expected: function.arity(),
+                got: arguments.len(),
+            });
+        }
+        let depth = self.call_stack.len();
+        if depth >= self.max_call_depth {
+            return Err(Error::StackOverflow { file: None, line });
+        }
+        self.function_calls += 1;
+        self.call_stack.push(CallFrame {
+            function_name: function.name().unwrap_or("<anonymous>").to_string(),
+            file: paren.and_then(Token::file),
+            line,
+        });
+        let previous = self.current_function.replace(Rc::clone(&function));
+        let (mut arguments, mut dropped) = (arguments, false);
+        // A tail call of the same function reuses this frame.
+        let result = loop {
+            let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+                function.call(self, arguments, warnings)
+            });
+            match self.tail_call.take() {
+                Some((next, drops)) if result.is_ok() => {
+                    self.function_calls += 1;
+                    arguments = next;
+                    dropped |= drops;
                 }
-                TokenType::BangEqual => Ok((left != right).into()),
-                TokenType::EqualEqual => Ok((left == right).into()),
-                _ => panic!("Invalid binary operator"),
-            }
-        }
-        Expr::Grouping(e) => evaluate(*e),
-        Expr::Literal(token) => match token.ty {
-            TokenType::Number(num) => Ok(num.into()),
-            TokenType::String(s) => Ok(s.into()),
-            TokenType::False => Ok(false.into()),
-            TokenType::True => Ok(true.into()),
-            TokenType::Nil => Ok(Value::Nil),
-            _ => panic!("Invalid literal value"),
-        },
-        Expr::Unary { operator, right } => {
-            let right = evaluate(*right)?;
-            match operator.ty {
-                TokenType::Minus => {
-                    let right = right.into_double(operator.line)?;
-                    Ok((-right).into())
+                _ => break result.map(|value| if dropped { Value::Nil } else { value }),
+            }
+        };
+        self.current_function = previous;
+        let result = result
+            .map_err(|e| e.at_line(line))
+            .and_then(|value| self.check_result(line, value));
+        // An error leaves the frames in place to show where it happened.
+        if result.is_ok() {
+            self.call_stack.truncate(depth);
+        }
+        result
+    }
+
+    /// Fails with [`Error::ResourceLimit`] if a string of `len` bytes would
+    /// be longer than [`with_max_string_length`](Self::with_max_string_length)
+    /// allows.
+    fn check_string_length(&self, line: usize, len: usize) -> Result<()> {
+        if len > self.max_string_length {
+            return Err(Error::ResourceLimit {
+                file: None,
+                line,
+                limit: "string",
+                max: self.max_string_length,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the length of a string built outside of concatenation, by a
+    /// cast or by a native function or method such as `upper` or `replace`.
+    /// Those build the whole string first, so the limit bounds what a
+    /// program keeps rather than every allocation.
+    fn check_result(&self, line: usize, value: Value) -> Result<Value> {
+        if let Value::String(s) = &value {
+            self.check_string_length(line, s.len())?;
+        }
+        Ok(value)
+    }
+
+    /// Whether `value` counts as true in a condition, such as the one
+    /// `token` belongs to. In strict mode only booleans are allowed.
+    fn truthy(&self, value: Value, token: &Token) -> Result<bool> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            value if !self.strict => Ok(value.is_truthy()),
+            value => Err(Error::TypeError {
+                file: token.file(),
+                line: token.line,
+                message: format!("Expected boolean but got {}.", value.kind()),
+            }),
+        }
+    }
+
+    /// Counts a statement, collecting garbage and checking the clock and the
+    /// interrupt signal every so often.
+    fn step(&mut self) -> Result<()> {
+        self.steps += 1;
+        if self.steps.is_multiple_of(STEPS_PER_GC) {
+            self.gc();
+        }
+        if self.steps.is_multiple_of(STEPS_PER_INTERRUPT_CHECK)
+            && self.interrupt.swap(false, Ordering::Relaxed)
+        {
+            return Err(Error::Interrupted);
+        }
+        match self.deadline {
+            Some(deadline)
+                if self.steps.is_multiple_of(STEPS_PER_DEADLINE_CHECK)
+                    && Instant::now() > deadline =>
+            {
+                Err(Error::Timeout)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs the module named by `path` in a fresh scope that sees only the
+    /// natives, then defines each of its exported variables in the current
+    /// scope. A default export is defined under the module's file name, so
+    /// `import "math.lox";` binds it to `math`.
+    fn import(&mut self, path: &Token, warnings: &mut Vec<Warning>) -> Result<()> {
+        let TokenType::String(relative) = &path.ty else {
+            unreachable!("the parser only accepts string literals after 'import'");
+        };
+        let error = |message| Error::Import {
+            file: path.file(),
+            line: path.line,
+            path: PathBuf::from(relative),
+            message,
+        };
+        if self.sandboxed {
+            return Err(error("Sandboxed code cannot import module"));
+        }
+        let file = match self.loading.last().and_then(|script| script.parent()) {
+            Some(dir) => dir.join(relative),
+            None => PathBuf::from(relative),
+        };
+        let file = file
+            .canonicalize()
+            .map_err(|_| error("Cannot read module"))?;
+        if self.loading.contains(&file) {
+            return Err(error("Circular import of module"));
+        }
+        // Name the module as the import does, which is shorter than its path.
+        let tokens = Scanner::new_with_file(&file)
+            .map_err(|_| error("Cannot read module"))?
+            .with_source_name(relative)
+            .scan_tokens()?;
+        let statements = Parser::new(tokens).parse()?;
+
+        let module = self
+            .heap
+            .track(Environment::new_enclosed(Rc::clone(&self.builtins)));
+        let previous = std::mem::replace(&mut self.environment, Rc::clone(&module));
+        let previous_default = self.default_export.take();
+        self.loading.push(file);
+        // A module's top-level `return` is not a tail call of the importer.
+        let function = self.current_function.take();
+        let result = self.execute_statements(&statements, warnings);
+        self.current_function = function;
+        self.loading.pop();
+        let default = std::mem::replace(&mut self.default_export, previous_default);
+        self.environment = previous;
+        result?;
+
+        let module = module.borrow();
+        let mut environment = self.environment.borrow_mut();
+        for statement in &statements {
+            let name = match statement {
+                Stmt::Export(declaration) => match declaration.as_ref() {
+                    Stmt::Function(function) => &function.name,
+                    Stmt::Var { name, .. } => name,
+                    _ => unreachable!("the parser only exports declarations"),
+                },
+                _ => continue,
+            };
+            if let Some(value) = module.values().get(&name.lexeme) {
+                environment.define(name.lexeme.clone(), value.clone());
+            }
+        }
+        if let Some(value) = default {
+            let name = Path::new(relative).file_stem().unwrap_or_default();
+            environment.define(name.to_string_lossy(), value);
+        }
+        Ok(())
+    }
+
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Environment,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Option<Value>> {
+        let environment = self.heap.track(environment);
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.execute_statements(statements, warnings);
+        self.environment = previous;
+        result
+    }
+
+    /// Evaluates `expr`, whose value the running function returns, or drops
+    /// when `dropped`. If it calls that same function, the call is left in
+    /// [`tail_call`](Self::tail_call) for [`call_at`](Self::call_at) to run
+    /// in place of the current one, so that the recursion does not nest,
+    /// and the value is nil until then.
+    fn evaluate_tail(
+        &mut self,
+        expr: &Expr,
+        dropped: bool,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        let Expr::Call {
+            callee,
+            paren,
+            arguments,
+        } = expr
+        else {
+            return self.evaluate(expr, warnings);
+        };
+        let callee = self.evaluate(callee, warnings)?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(argument, warnings))
+            .collect::<Result<Vec<_>>>()?;
+        let is_current = match (&callee, &self.current_function) {
+            (Value::Callable(function), Some(current)) => {
+                std::ptr::addr_eq(Rc::as_ptr(function), Rc::as_ptr(current))
+                    && arguments.len() == current.arity()
+            }
+            _ => false,
+        };
+        if is_current {
+            self.tail_call = Some((arguments, dropped));
+            return Ok(Value::Nil);
+        }
+        self.call_at(Some(paren), callee, arguments, warnings)
+            .map_err(|e| e.in_source(paren.source_name()))
+    }
+
+    pub fn evaluate(&mut self, expr: &Expr, warnings: &mut Vec<Warning>) -> Result<Value> {
+        // Errors are raised with a line only, so name their source here.
+        self.evaluate_expr(expr, warnings)
+            .map_err(|e| e.in_source(expr.token().source_name()))
+    }
+
+    fn evaluate_expr(&mut self, expr: &Expr, warnings: &mut Vec<Warning>) -> Result<Value> {
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left, warnings)?;
+                let right = self.evaluate(right, warnings)?;
+                match operator.ty {
+                    TokenType::Minus => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        Ok((left - right).into())
+                    }
+                    TokenType::Slash => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        if right == 0.0 {
+                            warnings.push(Warning {
+                                file: operator.file(),
+                                line: operator.line,
+                                column: operator.column,
+                                message: "Division by zero.".to_string(),
+                            });
+                        }
+                        Ok((left / right).into())
+                    }
+                    TokenType::Star => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        Ok((left * right).into())
+                    }
+                    TokenType::Plus => match (left, right) {
+                        (Value::Number(l), Value::Number(r)) => Ok((l + r).into()),
+                        (Value::String(l), Value::String(r)) => {
+                            self.check_string_length(operator.line, l.len() + r.len())?;
+                            Ok((l + &r).into())
+                        }
+                        (l, r) => Err(Value::add_type_error(operator.line, &l, &r)),
+                    },
+                    TokenType::Greater => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        Ok((left > right).into())
+                    }
+                    TokenType::GreaterEqual => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        Ok((left >= right).into())
+                    }
+                    TokenType::Less => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        Ok((left < right).into())
+                    }
+                    TokenType::LessEqual => {
+                        let left = left.into_double(operator.line)?;
+                        let right = right.into_double(operator.line)?;
+                        Ok((left <= right).into())
+                    }
+                    TokenType::BangEqual => Ok((left != right).into()),
+                    TokenType::EqualEqual => Ok((left == right).into()),
+                    _ => panic!("Invalid binary operator"),
                 }
-                TokenType::Bang => Ok(!right),
-                _ => panic!("Invalid unary operator"),
+            }
+            Expr::Grouping(e) => self.evaluate(e, warnings),
+            Expr::Sequence { left, right } => {
+                self.evaluate(left, warnings)?;
+                self.evaluate(right, warnings)
+            }
+            Expr::Literal(token) => match &token.ty {
+                TokenType::Number(num) => Ok((*num).into()),
+                TokenType::String(s) | TokenType::RawString(s) => Ok(s.clone().into()),
+                TokenType::False => Ok(false.into()),
+                TokenType::True => Ok(true.into()),
+                TokenType::Nil => Ok(Value::Nil),
+                _ => panic!("Invalid literal value"),
+            },
+            Expr::Unary { operator, right } => {
+                let right = self.evaluate(right, warnings)?;
+                match operator.ty {
+                    TokenType::Minus => {
+                        let right = right.into_double(operator.line)?;
+                        Ok((-right).into())
+                    }
+                    TokenType::Bang => Ok((!self.truthy(right, operator)?).into()),
+                    _ => panic!("Invalid unary operator"),
+                }
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+            } => {
+                let callee = self.evaluate(callee, warnings)?;
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.evaluate(argument, warnings))
+                    .collect::<Result<Vec<_>>>()?;
+                self.call_at(Some(paren), callee, arguments, warnings)
+            }
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.evaluate(left, warnings)?;
+                let short_circuits = match operator.ty {
+                    TokenType::Or => left.is_truthy(),
+                    TokenType::And => !left.is_truthy(),
+                    _ => unreachable!("the parser only builds logical expressions for and/or"),
+                };
+                if short_circuits {
+                    Ok(left)
+                } else {
+                    self.evaluate(right, warnings)
+                }
+            }
+            Expr::Variable(name) => self.environment.borrow().get(name),
+            Expr::Cast { expr, target_type } => {
+                let value = self.evaluate(expr, warnings)?.cast(target_type)?;
+                self.check_result(target_type.line, value)
+            }
+            Expr::Get { object, name } => {
+                let object = self.evaluate(object, warnings)?;
+                methods::get(object, name)
+            }
+            Expr::Lambda(declaration) => {
+                let function =
+                    LoxFunction::new(Rc::clone(declaration), Rc::clone(&self.environment));
+                Ok(Value::Callable(Rc::new(function)))
+            }
+            Expr::Assign { name, value } => {
+                let value = self.evaluate(value, warnings)?;
+                self.environment.borrow_mut().assign(name, value.clone())?;
+                Ok(value)
             }
         }
     }
 }
+
+/// Evaluates a single expression with no variables in scope.
+pub fn evaluate(expr: Expr, warnings: &mut Vec<Warning>) -> Result<Value> {
+    Interpreter::new().evaluate(&expr, warnings)
+}
+
+/// Writes `stmt` to stderr for [`Interpreter::enable_tracing`].
+fn trace(stmt: &Stmt) {
+    let (kind, line) = match stmt {
+        Stmt::Block(_) | Stmt::Export(_) => return,
+        Stmt::ExportDefault(expr) => ("export default", expr.line()),
+        Stmt::Expression(expr) => ("expression", expr.line()),
+        Stmt::Function(function) => ("fun", function.name.line),
+        Stmt::If { condition, .. } => ("if", condition.line()),
+        Stmt::Import { path } => ("import", path.line),
+        Stmt::Print(expr) => ("print", expr.line()),
+        Stmt::Return { keyword, .. } => ("return", keyword.line),
+        Stmt::Var { name, .. } => ("var", name.line),
+        Stmt::While { condition, .. } => ("while", condition.line()),
+        Stmt::With { bindings, .. } => ("with", bindings.first().map_or(0, |(name, _)| name.line)),
+    };
+    eprintln!("[line {line}] {kind}");
+}
+
+/// Evaluates a single expression with `environment` in scope, so that it
+/// can read and assign the variables bound there. Native functions are only
+/// visible if `environment` defines them.
+///
+/// A function expression captures the scope it is evaluated in, so when the
+/// result holds one, `environment` receives a copy of the scope's variables
+/// and later assignments made by the function are not seen there. Warnings
+/// are discarded.
+pub fn evaluate_with_environment(expr: Expr, environment: &mut Environment) -> Result<Value> {
+    let mut interpreter = Interpreter::new();
+    interpreter.environment = Rc::new(RefCell::new(std::mem::take(environment)));
+    let result = interpreter.evaluate(&expr, &mut vec![]);
+    let scope = std::mem::replace(&mut interpreter.environment, interpreter.globals.clone());
+    *environment = Rc::try_unwrap(scope)
+        .map(RefCell::into_inner)
+        .unwrap_or_else(|scope| scope.borrow().clone());
+    result
+}
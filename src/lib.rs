@@ -1,39 +1,379 @@
-use std::{io::Write, path::PathBuf};
+//! A tree-walk interpreter for the Lox language from
+//! [Crafting Interpreters](https://craftinginterpreters.com/).
+//!
+//! Expressions can be built without driving the [`Scanner`](scanner::Scanner)
+//! and [`Parser`](syntax::Parser) by hand, since [`Expr`](syntax::Expr)
+//! implements [`FromStr`](std::str::FromStr):
+//!
+//! ```
+//! use lox::syntax::Expr;
+//!
+//! let expr: Expr = "1 + 2 * 3".parse().unwrap();
+//! assert!(matches!(expr, Expr::Binary { .. }));
+//! ```
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
 
+mod bytes;
+pub mod callable;
+pub mod chunk;
+pub mod compiler;
+#[cfg(debug_assertions)]
+pub mod debug;
+pub mod environment;
 pub mod error;
-mod interpret;
-mod scanner;
-mod syntax;
+mod gc;
+pub mod grammar;
+pub mod interpret;
+pub mod lint;
+mod methods;
+pub mod prelude;
+mod random;
+#[cfg(feature = "regex")]
+mod regexp;
+pub mod repl;
+pub mod scanner;
+pub mod syntax;
+pub mod vm;
 
-use error::Result;
+use error::{Result, Warning};
+use interpret::{Interpreter, Value};
+use repl::Repl;
 use scanner::*;
 use syntax::*;
 
-pub fn run_file(path: impl Into<PathBuf>) -> Result<()> {
-    let source = std::fs::read_to_string(path.into())?;
-    run(source)
+/// Used by `#[lox_native]` to register natives.
+#[doc(hidden)]
+pub use inventory;
+
+/// How [`run_file`] treats a script. The default runs it normally.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunOptions {
+    /// Stop after parsing and linting, to check the script without running
+    /// it.
+    pub parse_only: bool,
+    /// Write each statement to stderr before running it, with the
+    /// interpreter's [tracing](Interpreter::enable_tracing) on.
+    pub trace: bool,
+    /// Run with the interpreter's
+    /// [strict mode](Interpreter::enable_strict_mode) on.
+    pub strict: bool,
+    /// Skip the warnings about shadowed variables.
+    pub allow_shadowing: bool,
+}
+
+/// Runs the script at `path`, printing any error and the warnings to
+/// stderr. An error in the script is shown against its source, and a
+/// runtime error is followed by the calls it happened in. The error is also
+/// returned, for the caller to pick an exit status from its
+/// [phase](error::Error::phase).
+pub fn run_file(path: impl Into<PathBuf>, options: RunOptions) -> Result<()> {
+    let path = path.into();
+    let mut lox = Lox::with_config(Config {
+        lint: !options.allow_shadowing,
+        ..Config::default()
+    });
+    if options.strict {
+        lox.interpreter().enable_strict_mode();
+    }
+    if options.trace {
+        lox.interpreter().enable_tracing();
+    }
+    let result = lox.load_file(path.clone()).and_then(|statements| {
+        if options.parse_only {
+            Ok(())
+        } else {
+            lox.execute(&statements)
+        }
+    });
+    if let Err(e) = &result {
+        // Errors may come from an imported module, so only those in the
+        // script itself are shown against its source.
+        match std::fs::read_to_string(&path) {
+            Ok(source) if e.source_name() == Some(path.as_path()) => {
+                eprintln!("{}", e.with_context(&source))
+            }
+            _ => eprintln!("{e}"),
+        }
+        if e.phase() == error::Phase::Runtime {
+            report_call_stack(lox.interpreter().call_stack());
+        }
+    }
+    report_warnings(&lox.take_warnings());
+    result
+}
+
+/// Splits `source` into tokens, ending with an EOF token.
+pub fn scan(source: &str) -> Result<Vec<Token>> {
+    Scanner::new(source.to_string()).scan_tokens()
+}
+
+/// Scans and parses `source` as a whole program.
+pub fn parse(source: &str) -> Result<Vec<Stmt>> {
+    Parser::new(scan(source)?).parse()
+}
+
+/// Scans and parses `source` as a single expression.
+pub fn parse_expr(source: &str) -> Result<Expr> {
+    Parser::new(scan(source)?).parse_expression()
+}
+
+/// Scans, parses and evaluates `source` as a single expression, with no
+/// variables in scope. Warnings are discarded.
+pub fn evaluate(source: &str) -> Result<Value> {
+    interpret::evaluate(parse_expr(source)?, &mut vec![])
+}
+
+/// Compiles an expression to bytecode that [`execute`] can run later without
+/// scanning or parsing it again.
+pub fn compile(source: &str) -> Result<Vec<u8>> {
+    let expr = parse_expr(source)?;
+    let chunk = compiler::Compiler::new().compile(expr)?;
+    chunk.to_bytes()
+}
+
+/// Runs bytecode produced by [`compile`] and prints the resulting value.
+pub fn execute(bytecode: &[u8]) -> Result<()> {
+    let chunk = chunk::Chunk::from_bytes(bytecode)?;
+    let value = vm::Vm::new(chunk).run()?;
+    println!("{value}");
+    Ok(())
+}
+
+/// Compiles the script at `path` and prints its bytecode without running it.
+#[cfg(debug_assertions)]
+pub fn disassemble_file(path: impl Into<PathBuf>) -> Result<()> {
+    let path = path.into();
+    let source = std::fs::read_to_string(&path)?;
+    let tokens = Scanner::new(source).scan_tokens()?;
+    let expr = Parser::new(tokens).parse_expression()?;
+    let chunk = compiler::Compiler::new().compile(expr)?;
+    debug::disassemble(&chunk, &path.display().to_string());
+    Ok(())
+}
+
+/// Everything about a [`Lox`] session that can be changed up front.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The longest string a program may build, in bytes.
+    pub max_string_length: usize,
+    /// How long each run may take before failing with
+    /// [`Error::Timeout`](error::Error::Timeout).
+    pub timeout: Option<Duration>,
+    /// Whether to check programs with the [linter](lint) before running
+    /// them. The REPL is never linted.
+    pub lint: bool,
+}
+
+impl Default for Config {
+    /// No limits, with linting on.
+    fn default() -> Self {
+        Self {
+            max_string_length: usize::MAX,
+            timeout: None,
+            lint: true,
+        }
+    }
+}
+
+/// A session that takes source code through scanning, parsing, linting and
+/// interpretation, keeping its global variables from one run to the next.
+///
+/// ```
+/// let mut lox = lox::Lox::new();
+/// lox.run_string("var answer = 6 * 7;").unwrap();
+/// assert_eq!(lox.eval("answer").unwrap(), 42.0.into());
+/// ```
+pub struct Lox {
+    config: Config,
+    interpreter: Interpreter,
+    warnings: Vec<Warning>,
 }
 
+impl Default for Lox {
+    fn default() -> Self {
+        Self::with_config(Config::default())
+    }
+}
+
+impl Lox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        let mut interpreter = Interpreter::new().with_max_string_length(config.max_string_length);
+        if let Some(timeout) = config.timeout {
+            interpreter = interpreter.with_timeout(timeout);
+        }
+        Self {
+            config,
+            interpreter,
+            warnings: vec![],
+        }
+    }
+
+    /// Seeds the `random` and `random_int` natives, for scripts that
+    /// should draw the same numbers every time they run.
+    pub fn with_random_seed(mut self, seed: u64) -> Self {
+        self.interpreter = std::mem::take(&mut self.interpreter).with_random_seed(seed);
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The interpreter the session runs code with, for access to its globals
+    /// and natives.
+    pub fn interpreter(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+
+    /// Runs the script at `path`. Its imports are resolved relative to the
+    /// directory it is in.
+    pub fn run_file(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        let statements = self.load_file(path.into())?;
+        self.execute(&statements)
+    }
+
+    /// Runs `source` as a whole program.
+    pub fn run_string(&mut self, source: &str) -> Result<()> {
+        let statements = self.load(parse(source)?);
+        self.execute(&statements)
+    }
+
+    /// Runs `source` and produces its value if it is a single expression, or
+    /// `nil` if it is a program.
+    pub fn eval(&mut self, source: &str) -> Result<Value> {
+        let tokens = scan(source)?;
+        if let Ok(expr) = Parser::new(tokens.clone()).parse_expression() {
+            return self.interpreter.evaluate_run(&expr, &mut self.warnings);
+        }
+        let statements = self.load(Parser::new(tokens).parse()?);
+        self.execute(&statements)?;
+        Ok(Value::Nil)
+    }
+
+    /// Reads and runs lines from stdin until it is closed, sharing this
+    /// session's variables.
+    pub fn repl(&mut self) {
+        let mut repl = Repl::with_interpreter(std::mem::take(&mut self.interpreter));
+        run_prompt_with(&mut repl);
+        self.interpreter = repl.into_interpreter();
+    }
+
+    /// Removes and returns the warnings raised since the last call,
+    /// including those the interpreter holds from runs made through
+    /// [`interpreter`](Self::interpreter).
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        let mut warnings = std::mem::take(&mut self.warnings);
+        warnings.extend(self.interpreter.take_warnings());
+        warnings
+    }
+
+    fn load_file(&mut self, path: PathBuf) -> Result<Vec<Stmt>> {
+        let tokens = Scanner::new_with_file(&path)?.scan_tokens()?;
+        let statements = Parser::new(tokens).parse()?;
+        self.interpreter = std::mem::take(&mut self.interpreter).with_script_path(path);
+        Ok(self.load(statements))
+    }
+
+    /// Lints `statements` if the configuration asks for it.
+    fn load(&mut self, statements: Vec<Stmt>) -> Vec<Stmt> {
+        if self.config.lint {
+            lint::ShadowLinter::new().check(&statements, &mut self.warnings);
+        }
+        statements
+    }
+
+    fn execute(&mut self, statements: &[Stmt]) -> Result<()> {
+        self.interpreter.execute_all(statements, &mut self.warnings)
+    }
+}
+
+const REPL_HELP: &str = "\
+:help   Show this list of commands
+:env    Print every variable in scope and its value
+:reset  Forget all variables defined so far";
+
 pub fn run_prompt() {
+    Lox::new().repl();
+}
+
+fn run_prompt_with(repl: &mut Repl) {
+    // Lines of input that are not yet a complete program.
+    let mut pending = String::new();
     loop {
         let mut line = String::new();
-        print!("> ");
+        print!("{}", if pending.is_empty() { "> " } else { "... " });
         std::io::stdout().flush().unwrap();
-        if std::io::stdin().read_line(&mut line).is_ok() {
-            if let Err(err) = run(line) {
-                eprintln!("{err}");
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        if pending.is_empty() {
+            if let Some(command) = line.trim().strip_prefix(':') {
+                run_command(repl, command);
+                continue;
             }
-        } else {
-            break;
         }
+
+        pending.push_str(&line);
+        match repl.eval_line(&pending) {
+            Ok(Some(value)) => println!("{value}"),
+            Ok(None) => {}
+            Err(err) if err.is_recoverable() => continue,
+            Err(err) => eprintln!("{err}"),
+        }
+        pending.clear();
+        report_warnings(&repl.take_warnings());
     }
 }
 
-fn run(source: String) -> Result<()> {
-    let scanner = Scanner::new(source);
-    let tokens = scanner.scan_tokens()?;
-    let parser = Parser::new(tokens);
-    let expr = parser.parse()?;
-    interpret::interpret(expr);
-    Ok(())
+fn run_command(repl: &mut Repl, command: &str) {
+    match command {
+        "help" => println!("{REPL_HELP}"),
+        "env" => {
+            for (name, value) in repl.state().iter() {
+                println!("{name} = {value}");
+            }
+        }
+        "reset" => repl.reset(),
+        _ => eprintln!("Unknown command ':{command}'. Type :help for a list of commands."),
+    }
+}
+
+/// Runs `source`, collecting any non-fatal diagnostics into `warnings`.
+/// Warnings found before a runtime error are still collected.
+pub fn run_string(source: String, warnings: &mut Vec<Warning>) -> Result<()> {
+    let mut lox = Lox::new();
+    let statements = lox.load(parse(&source)?);
+    let result = lox.execute(&statements);
+    warnings.extend(lox.take_warnings());
+    result
+}
+
+fn report_warnings(warnings: &[Warning]) {
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+}
+
+/// Prints the calls a runtime error happened in, innermost first. A frame
+/// repeated by recursion is printed once, then counted.
+fn report_call_stack(frames: &[interpret::CallFrame]) {
+    let mut frames = frames.iter().rev().peekable();
+    while let Some(frame) = frames.next() {
+        eprintln!("  {frame}");
+        let mut repeats = 0;
+        while frames.next_if_eq(&frame).is_some() {
+            repeats += 1;
+        }
+        if repeats > 0 {
+            eprintln!("  ... {repeats} more");
+        }
+    }
 }
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use crate::error::Warning;
+use crate::scanner::Token;
+use crate::syntax::{Expr, Function, Stmt};
+
+/// Warns when a `var` declaration hides a variable of the same name from an
+/// enclosing scope.
+///
+/// Redeclaring a variable in the same scope is not shadowing, and is left
+/// alone.
+#[derive(Debug, Default)]
+pub struct ShadowLinter {
+    /// The line each name was declared on, innermost scope last.
+    scopes: Vec<HashMap<String, usize>>,
+}
+
+impl ShadowLinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks a whole program, pushing a warning for every shadowing
+    /// declaration.
+    pub fn check(mut self, statements: &[Stmt], warnings: &mut Vec<Warning>) {
+        self.scopes.push(HashMap::new());
+        self.statements(statements, warnings);
+    }
+
+    fn statements(&mut self, statements: &[Stmt], warnings: &mut Vec<Warning>) {
+        for statement in statements {
+            self.statement(statement, warnings);
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt, warnings: &mut Vec<Warning>) {
+        match statement {
+            Stmt::Block(statements) => {
+                self.scopes.push(HashMap::new());
+                self.statements(statements, warnings);
+                self.scopes.pop();
+            }
+            Stmt::Function(function) => {
+                self.declare(&function.name);
+                self.function(function, warnings);
+            }
+            Stmt::Var { name, initializer } => {
+                if let Some(line) = self.outer_declaration(name) {
+                    warnings.push(Warning {
+                        file: name.file(),
+                        line: name.line,
+                        column: name.column,
+                        message: format!(
+                            "Variable '{}' shadows outer declaration at line {line}.",
+                            name.lexeme
+                        ),
+                    });
+                }
+                self.declare(name);
+                // A function stored in the variable can see it when called.
+                if let Some(initializer) = initializer {
+                    self.expression(initializer, warnings);
+                }
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expression(condition, warnings);
+                self.statement(then_branch, warnings);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch, warnings);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.expression(condition, warnings);
+                self.statement(body, warnings);
+            }
+            Stmt::With { bindings, body } => {
+                for (_, initializer) in bindings {
+                    self.expression(initializer, warnings);
+                }
+                self.scopes.push(HashMap::new());
+                for (name, _) in bindings {
+                    self.declare(name);
+                }
+                self.statement(body, warnings);
+                self.scopes.pop();
+            }
+            Stmt::Export(declaration) => self.statement(declaration, warnings),
+            Stmt::Expression(expr) | Stmt::ExportDefault(expr) | Stmt::Print(expr) => {
+                self.expression(expr, warnings)
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value, warnings);
+                }
+            }
+            Stmt::Import { .. } => {}
+        }
+    }
+
+    /// Checks the body of a function. Its parameters get a scope of their
+    /// own, outside the body's, so that a `var` hiding one is reported.
+    fn function(&mut self, function: &Function, warnings: &mut Vec<Warning>) {
+        self.scopes.push(HashMap::new());
+        for param in &function.params {
+            self.declare(param);
+        }
+        self.scopes.push(HashMap::new());
+        self.statements(&function.body, warnings);
+        self.scopes.pop();
+        self.scopes.pop();
+    }
+
+    /// Looks through an expression for `fun` expressions, whose bodies can
+    /// declare variables too.
+    fn expression(&mut self, expr: &Expr, warnings: &mut Vec<Warning>) {
+        match expr {
+            Expr::Lambda(function) => self.function(function, warnings),
+            Expr::Binary { left, right, .. }
+            | Expr::Logical { left, right, .. }
+            | Expr::Sequence { left, right } => {
+                self.expression(left, warnings);
+                self.expression(right, warnings);
+            }
+            Expr::Grouping(expr)
+            | Expr::Unary { right: expr, .. }
+            | Expr::Assign { value: expr, .. }
+            | Expr::Get { object: expr, .. }
+            | Expr::Cast { expr, .. } => self.expression(expr, warnings),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.expression(callee, warnings);
+                for argument in arguments {
+                    self.expression(argument, warnings);
+                }
+            }
+            Expr::Literal(_) | Expr::Variable(_) => {}
+        }
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), name.line);
+        }
+    }
+
+    /// Where `name` was declared in an enclosing scope, unless the current
+    /// scope already declares it.
+    fn outer_declaration(&self, name: &Token) -> Option<usize> {
+        let (current, outer) = self.scopes.split_last()?;
+        if current.contains_key(&name.lexeme) {
+            return None;
+        }
+        outer
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme).copied())
+    }
+}
@@ -1,14 +1,51 @@
+use lox::error::Phase;
 use lox::*;
 
 fn main() -> Result<(), lox::error::Error> {
-    let mut args = std::env::args();
-    match args.len() {
-        l if l > 2 => {
-            eprintln!("Usage: {} [script]", args.next().unwrap());
-            std::process::exit(64);
-        }
-        2 => run_file(args.nth(1).unwrap())?,
-        _ => run_prompt(),
+    let args: Vec<String> = std::env::args().collect();
+    match args.as_slice() {
+        #[cfg(debug_assertions)]
+        [_, flag, path] if flag == "--disassemble" => disassemble_file(path)?,
+        [_] => run_prompt(),
+        // A script's path never starts with `--`, so flags without one are
+        // not mistaken for it.
+        [program, flags @ .., path] if !path.starts_with("--") => match run_options(flags) {
+            Some(options) => run_script(path, options),
+            None => usage(program),
+        },
+        [program, ..] => usage(program),
+        [] => unreachable!("missing program name"),
     }
     Ok(())
 }
+
+fn usage(program: &str) -> ! {
+    eprintln!("Usage: {program} [--allow-shadowing] [--trace] [script]");
+    std::process::exit(64);
+}
+
+/// Reads the flags before a script's path, or `None` for one that is not
+/// known.
+fn run_options(flags: &[String]) -> Option<RunOptions> {
+    let mut options = RunOptions::default();
+    for flag in flags {
+        match flag.as_str() {
+            "--allow-shadowing" => options.allow_shadowing = true,
+            "--trace" => options.trace = true,
+            _ => return None,
+        }
+    }
+    Some(options)
+}
+
+/// Runs the script at `path`, exiting with the status of sysexits.h for
+/// its error's phase, as the book does.
+fn run_script(path: &str, options: RunOptions) {
+    if let Err(e) = lox::run_file(path, options) {
+        std::process::exit(match e.phase() {
+            Phase::IO => 66,
+            Phase::Scan | Phase::Parse | Phase::Resolve => 65,
+            Phase::Runtime => 70,
+        });
+    }
+}
@@ -0,0 +1,249 @@
+//! Methods of built-in types, such as `"hello".len()`, which are what a
+//! property read finds on a value that is not an instance.
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+
+use crate::callable::Callable;
+use crate::error::{Error, Result, Warning};
+use crate::interpret::{Interpreter, Value};
+use crate::scanner::Token;
+
+/// A method of the built-in type `T`. `function` gets the receiver, the
+/// arguments, and the line of the property read for reporting errors.
+struct Method<T> {
+    name: &'static str,
+    arity: usize,
+    function: fn(&T, Vec<Value>, usize) -> Result<Value>,
+}
+
+/// `split` is missing until Lox has arrays to return. `slice` counts
+/// characters, not bytes, as `len` does. With the `regex` feature, `match`
+/// finds a regexp, and `replace` takes a regexp as well as a string; the
+/// [`regexp`](crate::regexp) module describes what `match` returns.
+static STRING_METHODS: &[Method<String>] = &[
+    Method {
+        name: "len",
+        arity: 0,
+        function: |s, _, _| Ok((s.chars().count() as f64).into()),
+    },
+    Method {
+        name: "upper",
+        arity: 0,
+        function: |s, _, _| Ok(s.to_uppercase().into()),
+    },
+    Method {
+        name: "lower",
+        arity: 0,
+        function: |s, _, _| Ok(s.to_lowercase().into()),
+    },
+    Method {
+        name: "trim",
+        arity: 0,
+        function: |s, _, _| Ok(s.trim().to_string().into()),
+    },
+    Method {
+        name: "starts_with",
+        arity: 1,
+        function: |s, arguments, line| {
+            let [prefix] = strings(arguments, line)?;
+            Ok(s.starts_with(&prefix).into())
+        },
+    },
+    Method {
+        name: "ends_with",
+        arity: 1,
+        function: |s, arguments, line| {
+            let [suffix] = strings(arguments, line)?;
+            Ok(s.ends_with(&suffix).into())
+        },
+    },
+    Method {
+        name: "contains",
+        arity: 1,
+        function: |s, arguments, line| {
+            let [needle] = strings(arguments, line)?;
+            Ok(s.contains(&needle).into())
+        },
+    },
+    #[cfg(feature = "regex")]
+    Method {
+        name: "match",
+        arity: 1,
+        function: |s, arguments, line| {
+            let [regexp] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+            match regexp {
+                Value::RegExp(regexp) => Ok(crate::regexp::find(s, &regexp)),
+                value => Err(Error::TypeError {
+                    file: None,
+                    line,
+                    message: format!("Expected regexp but got {}.", value.kind()),
+                }),
+            }
+        },
+    },
+    Method {
+        name: "replace",
+        arity: 2,
+        function: |s, arguments, line| {
+            let [old, new] = <[Value; 2]>::try_from(arguments).expect("arity is checked");
+            let [new] = strings(vec![new], line)?;
+            match old {
+                Value::String(old) => Ok(s.replace(&old, &new).into()),
+                // `new` may refer to groups, such as `$1`.
+                #[cfg(feature = "regex")]
+                Value::RegExp(regexp) => {
+                    Ok(regexp.replace_all(s, new.as_str()).into_owned().into())
+                }
+                #[cfg(feature = "regex")]
+                value => Err(Error::TypeError {
+                    file: None,
+                    line,
+                    message: format!("Expected string or regexp but got {}.", value.kind()),
+                }),
+                #[cfg(not(feature = "regex"))]
+                value => Err(Error::TypeError {
+                    file: None,
+                    line,
+                    message: format!("Expected string but got {}.", value.kind()),
+                }),
+            }
+        },
+    },
+    Method {
+        name: "slice",
+        arity: 2,
+        function: |s, arguments, line| {
+            let [start, end] = <[Value; 2]>::try_from(arguments).expect("arity is checked");
+            let (start, end) = (start.into_double(line)?, end.into_double(line)?);
+            let len = s.chars().count();
+            let whole = start.fract() == 0.0 && end.fract() == 0.0;
+            if !whole || start < 0.0 || start > end || end > len as f64 {
+                return Err(Error::TypeError {
+                    file: None,
+                    line,
+                    message: format!(
+                        "Cannot slice {start} to {end} from a string of length {len}."
+                    ),
+                });
+            }
+            let (start, end) = (start as usize, end as usize);
+            Ok(s.chars()
+                .skip(start)
+                .take(end - start)
+                .collect::<String>()
+                .into())
+        },
+    },
+];
+
+static NUMBER_METHODS: &[Method<f64>] = &[
+    Method {
+        name: "to_string",
+        arity: 0,
+        function: |n, _, _| Ok(Value::Number(*n).to_lox_string().into()),
+    },
+    Method {
+        name: "floor",
+        arity: 0,
+        function: |n, _, _| Ok(n.floor().into()),
+    },
+    Method {
+        name: "ceil",
+        arity: 0,
+        function: |n, _, _| Ok(n.ceil().into()),
+    },
+    Method {
+        name: "abs",
+        arity: 0,
+        function: |n, _, _| Ok(n.abs().into()),
+    },
+];
+
+/// The arguments of a method that only takes strings.
+fn strings<const N: usize>(arguments: Vec<Value>, line: usize) -> Result<[String; N]> {
+    let strings = arguments
+        .into_iter()
+        .map(|argument| match argument {
+            Value::String(s) => Ok(s),
+            value => Err(Error::TypeError {
+                file: None,
+                line,
+                message: format!("Expected string but got {}.", value.kind()),
+            }),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(strings.try_into().expect("arity is checked"))
+}
+
+/// A method read from a value, which remembers the value to call it on.
+struct BoundMethod<T: 'static> {
+    receiver: T,
+    line: usize,
+    method: &'static Method<T>,
+}
+
+impl<T: Debug> Debug for BoundMethod<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoundMethod")
+            .field("receiver", &self.receiver)
+            .field("name", &self.method.name)
+            .finish()
+    }
+}
+
+impl<T> Display for BoundMethod<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+impl<T: Debug> Callable for BoundMethod<T> {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+        _warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        (self.method.function)(&self.receiver, arguments, self.line)
+    }
+
+    fn arity(&self) -> usize {
+        self.method.arity
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(self.method.name)
+    }
+}
+
+/// Reads the property `name` of `object`.
+pub(crate) fn get(object: Value, name: &Token) -> Result<Value> {
+    match object {
+        Value::String(s) => bind(STRING_METHODS, s, name),
+        Value::Number(n) => bind(NUMBER_METHODS, n, name),
+        value => Err(Error::TypeError {
+            file: name.file(),
+            line: name.line,
+            message: format!(
+                "Only strings and numbers have properties, not {}.",
+                value.kind()
+            ),
+        }),
+    }
+}
+
+fn bind<T: Debug>(methods: &'static [Method<T>], receiver: T, name: &Token) -> Result<Value> {
+    let method = methods
+        .iter()
+        .find(|method| method.name == name.lexeme)
+        .ok_or_else(|| Error::TypeError {
+            file: name.file(),
+            line: name.line,
+            message: format!("Undefined property '{}'.", name.lexeme),
+        })?;
+    Ok(Value::Callable(Rc::new(BoundMethod {
+        receiver,
+        line: name.line,
+        method,
+    })))
+}
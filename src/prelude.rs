@@ -0,0 +1,16 @@
+//! The types most embedding code needs, for importing all at once:
+//!
+//! ```
+//! use lox::prelude::*;
+//!
+//! let mut lox = Lox::new();
+//! let value: Result<Value> = lox.eval("1 + 2");
+//! assert_eq!(value.unwrap(), Value::Number(3.0));
+//! ```
+//!
+//! Anything less common is still imported from its own module.
+pub use crate::error::{Error, Result};
+pub use crate::interpret::{Interpreter, Value};
+pub use crate::scanner::{Token, TokenType};
+pub use crate::syntax::{Expr, Stmt};
+pub use crate::Lox;
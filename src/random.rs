@@ -0,0 +1,183 @@
+//! The `random` and `random_int` natives, which share one xoshiro256+
+//! generator so that a seed fixes every number a program draws.
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::callable::Callable;
+use crate::error::{Error, Result, Warning};
+use crate::interpret::{Interpreter, Value};
+
+/// The xoshiro256+ generator by Blackman and Vigna, which is fast and good
+/// enough for floating-point numbers, but not for cryptography.
+#[derive(Debug)]
+struct Xoshiro256Plus {
+    state: [u64; 4],
+}
+
+impl Xoshiro256Plus {
+    /// Expands `seed` into a full state with SplitMix64, as the authors
+    /// recommend, so that similar seeds give unrelated sequences.
+    fn new(mut seed: u64) -> Self {
+        let mut state = [0; 4];
+        for word in &mut state {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *word = z ^ (z >> 31);
+        }
+        Self { state }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s0.wrapping_add(*s3);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// A number in `[0, 1)`, from the top 53 bits, which are the best ones.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A number in `[0, bound]`, drawing again rather than taking a value
+    /// from the last, incomplete run of `bound + 1` numbers so that every
+    /// result is equally likely.
+    fn next_u64_up_to(&mut self, bound: u64) -> u64 {
+        let Some(n) = bound.checked_add(1) else {
+            return self.next_u64();
+        };
+        // 2^64 mod n, the size of the incomplete run.
+        let excess = (u64::MAX % n + 1) % n;
+        loop {
+            let x = self.next_u64();
+            if x <= u64::MAX - excess {
+                return x % n;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// `random()`, a number in `[0, 1)`.
+    Fraction,
+    /// `random_int(low, high)`, an integer in `[low, high]`.
+    Integer,
+}
+
+/// One of the random natives, holding the generator it shares with the
+/// other.
+#[derive(Debug)]
+pub(crate) struct RandomFunction {
+    kind: Kind,
+    generator: Rc<RefCell<Xoshiro256Plus>>,
+}
+
+impl RandomFunction {
+    /// The natives, with their names, drawing from a generator seeded with
+    /// `seed`.
+    pub(crate) fn natives(seed: u64) -> [(&'static str, RandomFunction); 2] {
+        let generator = Rc::new(RefCell::new(Xoshiro256Plus::new(seed)));
+        [
+            (
+                "random",
+                RandomFunction {
+                    kind: Kind::Fraction,
+                    generator: Rc::clone(&generator),
+                },
+            ),
+            (
+                "random_int",
+                RandomFunction {
+                    kind: Kind::Integer,
+                    generator,
+                },
+            ),
+        ]
+    }
+}
+
+impl Callable for RandomFunction {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+        _warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        let Kind::Integer = self.kind else {
+            return Ok(self.generator.borrow_mut().next_f64().into());
+        };
+        let [low, high] = <[Value; 2]>::try_from(arguments).expect("arity is checked");
+        let low = low.into_double(0)?.ceil();
+        let high = high.into_double(0)?.floor();
+        // Errors are at line 0 until the call fills in its own.
+        if !low.is_finite() || !high.is_finite() {
+            return Err(Error::TypeError {
+                file: None,
+                line: 0,
+                message: format!("Expected finite bounds but got {low} and {high}."),
+            });
+        }
+        if low > high {
+            return Err(Error::TypeError {
+                file: None,
+                line: 0,
+                message: format!("No integer lies between {low} and {high}."),
+            });
+        }
+        let mut generator = self.generator.borrow_mut();
+        // Bounds within 2^64 of each other are drawn between exactly; the
+        // result rounds to a number no greater than `high`, which is
+        // itself a number. Casts saturate, so bounds past the i128 range
+        // must skip this.
+        let in_range = |n: f64| (i128::MIN as f64..i128::MAX as f64).contains(&n);
+        if in_range(low) && in_range(high) {
+            let (first, last) = (low as i128, high as i128);
+            if let Some(span) = last.checked_sub(first).and_then(|s| u64::try_from(s).ok()) {
+                let offset = generator.next_u64_up_to(span);
+                return Ok(((first + i128::from(offset)) as f64).into());
+            }
+        }
+        // Wider bounds are far past 2^53, where numbers are spaced too far
+        // apart for every integer to be drawn, so scaling a fraction is as
+        // good. It can round up past `high`, and the width itself can
+        // overflow to infinity when the bounds are near the largest number.
+        let fraction = generator.next_f64();
+        let width = high - low;
+        let drawn = if width.is_finite() {
+            low + (fraction * (width + 1.0)).floor()
+        } else {
+            (low - low * fraction + high * fraction).floor()
+        };
+        Ok(drawn.clamp(low, high).into())
+    }
+
+    fn arity(&self) -> usize {
+        match self.kind {
+            Kind::Fraction => 0,
+            Kind::Integer => 2,
+        }
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self.kind {
+            Kind::Fraction => Some("random"),
+            Kind::Integer => Some("random_int"),
+        }
+    }
+}
+
+impl Display for RandomFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
@@ -0,0 +1,90 @@
+//! The `regexp` native and the regexp forms of the string methods `match`
+//! and `replace`, built on the `regex` crate and only compiled with the
+//! `regex` feature.
+//!
+//! Lox has no arrays yet to hold the capture groups of a match, so
+//! `"text".match(re)` gives `nil` when nothing matches, and otherwise a
+//! function of a group number: `m(0)` is the matched text and `m(n)` is
+//! group `n`, or `nil` if that group took no part in the match. Errors are
+//! raised at line 0, which the call replaces with its own.
+use std::fmt::Display;
+use std::rc::Rc;
+
+use regex::Regex;
+
+use crate::callable::{Callable, NativeFunction};
+use crate::error::{Error, Result, Warning};
+use crate::interpret::{Interpreter, Value};
+
+pub(crate) fn natives() -> [NativeFunction; 1] {
+    [NativeFunction::new("regexp", 1, |arguments| {
+        let [pattern] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+        let Value::String(pattern) = pattern else {
+            return Err(Error::TypeError {
+                file: None,
+                line: 0,
+                message: format!("Expected string but got {}.", pattern.kind()),
+            });
+        };
+        let regexp = Regex::new(&pattern).map_err(|e| Error::chain(0, e))?;
+        Ok(Value::RegExp(Rc::new(regexp)))
+    })]
+}
+
+/// The first match of `regexp` in `text`, as the function of its groups
+/// that `match` returns, or `nil`.
+pub(crate) fn find(text: &str, regexp: &Regex) -> Value {
+    regexp.captures(text).map_or(Value::Nil, |captures| {
+        let groups = captures
+            .iter()
+            .map(|group| group.map(|found| found.as_str().to_string()))
+            .collect();
+        Value::Callable(Rc::new(Match { groups }))
+    })
+}
+
+/// What `match` returns for a match: called with `n`, it gives group `n`.
+#[derive(Debug)]
+struct Match {
+    /// The whole match, then each group in order.
+    groups: Vec<Option<String>>,
+}
+
+impl Callable for Match {
+    fn call(
+        &self,
+        _interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+        _warnings: &mut Vec<Warning>,
+    ) -> Result<Value> {
+        let [group] = <[Value; 1]>::try_from(arguments).expect("arity is checked");
+        let group = group.into_double(0)?;
+        if group.fract() != 0.0 || group < 0.0 || group >= self.groups.len() as f64 {
+            return Err(Error::TypeError {
+                file: None,
+                line: 0,
+                message: format!(
+                    "No group {group} in a match with {} groups.",
+                    self.groups.len() - 1
+                ),
+            });
+        }
+        Ok(self.groups[group as usize]
+            .clone()
+            .map_or(Value::Nil, Value::from))
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some("match")
+    }
+}
+
+impl Display for Match {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
@@ -0,0 +1,86 @@
+use std::cell::Ref;
+
+use crate::environment::Environment;
+use crate::error::{Error, Result, Warning};
+use crate::interpret::{Interpreter, Value};
+use crate::scanner::Scanner;
+use crate::syntax::Parser;
+
+/// An interactive session that can be driven one line at a time.
+#[derive(Default)]
+pub struct Repl {
+    interpreter: Interpreter,
+    history: Vec<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A session that runs its input with `interpreter`, keeping the
+    /// variables and limits it already has.
+    pub fn with_interpreter(interpreter: Interpreter) -> Self {
+        Self {
+            interpreter,
+            ..Self::default()
+        }
+    }
+
+    /// Ends the session, handing back its interpreter.
+    pub fn into_interpreter(self) -> Interpreter {
+        self.interpreter
+    }
+
+    /// Runs one line of input. A bare expression produces its value, while
+    /// statements produce `None`.
+    ///
+    /// Input that fails only because it is incomplete is left out of the
+    /// history, so the caller can append more and try again.
+    pub fn eval_line(&mut self, line: &str) -> Result<Option<Value>> {
+        let result = self.eval(line);
+        if !result.as_ref().is_err_and(Error::is_recoverable) {
+            self.history.push(line.to_string());
+        }
+        result
+    }
+
+    /// Errors name the source `<repl>`.
+    fn eval(&mut self, line: &str) -> Result<Option<Value>> {
+        let tokens = Scanner::new(line.to_string())
+            .with_source_name("<repl>")
+            .scan_tokens()?;
+        if let Ok(expr) = Parser::new(tokens.clone()).parse_expression() {
+            return self
+                .interpreter
+                .evaluate_run(&expr, &mut self.warnings)
+                .map(Some);
+        }
+        let statements = Parser::new(tokens).parse()?;
+        self.interpreter
+            .execute_all(&statements, &mut self.warnings)?;
+        Ok(None)
+    }
+
+    /// The global scope, holding every variable defined so far.
+    pub fn state(&self) -> Ref<'_, Environment> {
+        self.interpreter.globals()
+    }
+
+    /// Every complete input passed to [`eval_line`](Self::eval_line), oldest
+    /// first.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Removes and returns the warnings raised since the last call.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Forgets every variable defined so far, keeping the history.
+    pub fn reset(&mut self) {
+        self.interpreter.reset();
+    }
+}
@@ -1,9 +1,12 @@
 use phf::phf_map;
 use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use crate::error::{Error, Result};
 
 #[derive(Clone, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum TokenType {
     // Single-character tokens
     LeftParen,
@@ -31,16 +34,19 @@ pub enum TokenType {
     // Literals
     Identifier(String),
     String(String),
+    RawString(String),
     Number(f64),
 
     // Keywords
     And,
     Class,
     Else,
+    Export,
     False,
     Fun,
     For,
     If,
+    Import,
     Nil,
     Or,
     Print,
@@ -60,16 +66,67 @@ impl TokenType {
         use std::mem::discriminant;
         discriminant(self) == discriminant(other)
     }
+
+    /// How the token is described in error messages: quoted source text for
+    /// punctuation, operators and keywords, and a kind for everything else.
+    pub fn display_name(&self) -> &'static str {
+        use TokenType::*;
+        match self {
+            LeftParen => "'('",
+            RightParen => "')'",
+            LeftBrace => "'{'",
+            RightBrace => "'}'",
+            Comma => "','",
+            Dot => "'.'",
+            Minus => "'-'",
+            Plus => "'+'",
+            Semicolon => "';'",
+            Slash => "'/'",
+            Star => "'*'",
+            Bang => "'!'",
+            BangEqual => "'!='",
+            Equal => "'='",
+            EqualEqual => "'=='",
+            Greater => "'>'",
+            GreaterEqual => "'>='",
+            Less => "'<'",
+            LessEqual => "'<='",
+            Identifier(_) => "identifier",
+            String(_) | RawString(_) => "string literal",
+            Number(_) => "number",
+            And => "'and'",
+            Class => "'class'",
+            Else => "'else'",
+            Export => "'export'",
+            False => "'false'",
+            Fun => "'fun'",
+            For => "'for'",
+            If => "'if'",
+            Import => "'import'",
+            Nil => "'nil'",
+            Or => "'or'",
+            Print => "'print'",
+            Return => "'return'",
+            Super => "'super'",
+            This => "'this'",
+            True => "'true'",
+            Var => "'var'",
+            While => "'while'",
+            Eof => "end of file",
+        }
+    }
 }
 
 static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "and" => TokenType::And,
     "class" => TokenType::Class,
     "else" => TokenType::Else,
+    "export" => TokenType::Export,
     "false" => TokenType::False,
     "for" => TokenType::For,
     "fun" => TokenType::Fun,
     "if" => TokenType::If,
+    "import" => TokenType::Import,
     "nil" => TokenType::Nil,
     "or" => TokenType::Or,
     "print" => TokenType::Print,
@@ -81,11 +138,214 @@ static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "while" => TokenType::While,
 };
 
+/// Every reserved word in the language.
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORDS.keys().copied()
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub(crate) ty: TokenType,
     pub(crate) lexeme: String,
     pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    pub(crate) source_name: Option<Arc<Path>>,
+}
+
+impl Token {
+    pub fn ty(&self) -> &TokenType {
+        &self.ty
+    }
+
+    pub fn lexeme(&self) -> &str {
+        &self.lexeme
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based byte offset of the token within its line.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The token's `(start, end)` byte offsets in the source, end exclusive.
+    pub fn span(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    /// The name of the source the token was scanned from, set by
+    /// [`Scanner::new_with_file`] or [`Scanner::with_source_name`].
+    pub fn source_name(&self) -> Option<&Path> {
+        self.source_name.as_deref()
+    }
+
+    /// The source name for an error about this token.
+    pub(crate) fn file(&self) -> Option<PathBuf> {
+        self.source_name().map(Path::to_path_buf)
+    }
+}
+
+/// A coarse category of source text, for syntax highlighting.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenClass {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Operator,
+    Punctuation,
+    Comment,
+    Whitespace,
+}
+
+impl Token {
+    pub fn class(&self) -> TokenClass {
+        use TokenType::*;
+        match self.ty {
+            LeftParen | RightParen | LeftBrace | RightBrace | Comma | Dot | Semicolon => {
+                TokenClass::Punctuation
+            }
+            Minus | Plus | Slash | Star | Bang | BangEqual | Equal | EqualEqual | Greater
+            | GreaterEqual | Less | LessEqual => TokenClass::Operator,
+            Identifier(_) => TokenClass::Identifier,
+            String(_) | RawString(_) => TokenClass::String,
+            Number(_) => TokenClass::Number,
+            And | Class | Else | Export | False | Fun | For | If | Import | Nil | Or | Print
+            | Return | Super | This | True | Var | While => TokenClass::Keyword,
+            Eof => TokenClass::Whitespace,
+        }
+    }
+
+    /// Whether this is a reserved word, including `true`, `false` and `nil`.
+    pub fn is_keyword(&self) -> bool {
+        self.class() == TokenClass::Keyword
+    }
+
+    /// Whether this is a number, string, `true`, `false` or `nil`.
+    pub fn is_literal(&self) -> bool {
+        use TokenType::*;
+        matches!(
+            self.ty,
+            Number(_) | String(_) | RawString(_) | True | False | Nil
+        )
+    }
+
+    pub fn is_operator(&self) -> bool {
+        self.class() == TokenClass::Operator
+    }
+
+    pub fn is_punctuation(&self) -> bool {
+        self.class() == TokenClass::Punctuation
+    }
+}
+
+/// Splits `source` into `(class, start_byte, end_byte)` spans without
+/// parsing it. Text that fails to scan is left out, and scanning resumes
+/// after it.
+pub fn classify_tokens(source: &str) -> Vec<(TokenClass, usize, usize)> {
+    let mut scanner = Scanner::new(source.to_string());
+    let mut spans: Vec<(TokenClass, usize, usize)> = vec![];
+    scanner.skip_shebang();
+    if scanner.current > 0 {
+        let end = source.find('\n').unwrap_or(source.len());
+        spans.push((TokenClass::Comment, 0, end));
+        if end < scanner.current {
+            spans.push((TokenClass::Whitespace, end, scanner.current));
+        }
+    }
+
+    while !scanner.is_at_end() {
+        let (class, start, end) = match scanner.next_lexeme() {
+            Ok(Some(token)) => (token.class(), token.start, token.end),
+            Ok(None) if scanner.current_lexeme().starts_with("//") => {
+                (TokenClass::Comment, scanner.start, scanner.current)
+            }
+            Ok(None) => (TokenClass::Whitespace, scanner.start, scanner.current),
+            Err(_) => continue,
+        };
+        match spans.last_mut() {
+            Some((TokenClass::Whitespace, _, last_end))
+                if class == TokenClass::Whitespace && *last_end == start =>
+            {
+                *last_end = end
+            }
+            _ => spans.push((class, start, end)),
+        }
+    }
+    spans
+}
+
+/// Shows the token as it would be written in Lox source. Strings are
+/// quoted, with escape sequences where needed so that scanning the output
+/// gives back the same string, and the end of file has no text.
+impl Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use TokenType::*;
+        let text = match self {
+            LeftParen => "(",
+            RightParen => ")",
+            LeftBrace => "{",
+            RightBrace => "}",
+            Comma => ",",
+            Dot => ".",
+            Minus => "-",
+            Plus => "+",
+            Semicolon => ";",
+            Slash => "/",
+            Star => "*",
+            Bang => "!",
+            BangEqual => "!=",
+            Equal => "=",
+            EqualEqual => "==",
+            Greater => ">",
+            GreaterEqual => ">=",
+            Less => "<",
+            LessEqual => "<=",
+            Identifier(name) => name,
+            String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\0' => f.write_str("\\0")?,
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        c if c.is_control() => write!(f, "\\u{{{:X}}}", c as u32)?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                "\""
+            }
+            RawString(s) => return write!(f, "\"\"\"{s}\"\"\""),
+            Number(n) => return write!(f, "{n}"),
+            And => "and",
+            Class => "class",
+            Else => "else",
+            Export => "export",
+            False => "false",
+            Fun => "fun",
+            For => "for",
+            If => "if",
+            Import => "import",
+            Nil => "nil",
+            Or => "or",
+            Print => "print",
+            Return => "return",
+            Super => "super",
+            This => "this",
+            True => "true",
+            Var => "var",
+            While => "while",
+            Eof => "",
+        };
+        f.write_str(text)
+    }
 }
 
 impl Display for Token {
@@ -96,9 +356,17 @@ impl Display for Token {
 
 pub struct Scanner {
     source: String,
+    source_name: Option<Arc<Path>>,
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    column: usize,
+    /// The result of the last successful scan, ordered by position, so that
+    /// [`rescan_range`](Self::rescan_range) can reuse it.
+    tokens: Vec<Token>,
+    /// A token read by [`peek_token`](Self::peek_token) and not yet taken.
+    peeked: Option<Token>,
 }
 
 const SKIP_TOKEN: Result<Option<Token>> = Ok(None);
@@ -107,28 +375,281 @@ impl Scanner {
     pub fn new(source: String) -> Self {
         Self {
             source,
+            source_name: None,
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            column: 1,
+            tokens: vec![],
+            peeked: None,
         }
     }
 
-    pub fn scan_tokens(mut self) -> Result<Vec<Token>> {
+    /// Reads the source from `path`, which names it as
+    /// [`with_source_name`](Self::with_source_name) does.
+    pub fn new_with_file(path: &Path) -> std::io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Ok(Self::new(source).with_source_name(path))
+    }
+
+    /// Names the source, such as `<repl>` or the path of a file. Every token
+    /// carries the name, and errors found in the source report it along
+    /// with the line, as `[file.lox:3]`, or `[file.lox:3:14]` when the
+    /// column is known. Naming a source does not change which errors it
+    /// raises.
+    pub fn with_source_name(mut self, name: impl Into<PathBuf>) -> Self {
+        self.source_name = Some(Arc::from(name.into()));
+        self
+    }
+
+    /// Rewinds to the start of the source so it can be scanned again.
+    pub fn reset(&mut self) {
+        self.start = 0;
+        self.current = 0;
+        self.line = 1;
+        self.line_start = 0;
+        self.column = 1;
+        self.peeked = None;
+    }
+
+    /// Skips a `#!` line at the very start of the source, so that scripts
+    /// can be run directly on Unix. Scanning from the start does this
+    /// already; it does nothing anywhere else.
+    pub fn skip_shebang(&mut self) {
+        if self.current != 0 || !self.source.starts_with("#!") {
+            return;
+        }
+        match self.source.find('\n') {
+            Some(i) => {
+                self.current = i + 1;
+                self.newline();
+            }
+            None => self.current = self.source.len(),
+        }
+    }
+
+    /// Scans the next token, skipping whitespace and comments, or produces
+    /// `None` once the source is used up. Unlike
+    /// [`scan_tokens`](Self::scan_tokens), no EOF token is produced.
+    pub fn next_token(&mut self) -> Result<Option<Token>> {
+        if let Some(token) = self.peeked.take() {
+            return Ok(Some(token));
+        }
+        self.skip_shebang();
+        while !self.is_at_end() {
+            if let Some(token) = self.next_lexeme()? {
+                return Ok(Some(token));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The token [`next_token`](Self::next_token) will produce, without
+    /// consuming it.
+    pub fn peek_token(&mut self) -> Result<Option<Token>> {
+        if self.peeked.is_none() {
+            self.peeked = self.next_token()?;
+        }
+        Ok(self.peeked.clone())
+    }
+
+    pub fn scan_tokens(&mut self) -> Result<Vec<Token>> {
+        self.tokens.clear();
         let mut tokens = vec![];
+        self.scan_rest(&mut tokens)?;
+        self.tokens = tokens.clone();
+        Ok(tokens)
+    }
+
+    /// Like [`scan_tokens`](Self::scan_tokens), but fills `tokens` instead
+    /// of allocating a new vector, so one buffer can serve many scans. The
+    /// buffer is cleared first, and holds the tokens scanned before the
+    /// error if scanning fails.
+    pub fn scan_into(mut self, tokens: &mut Vec<Token>) -> Result<()> {
+        tokens.clear();
+        self.scan_rest(tokens)
+    }
 
+    fn scan_rest(&mut self, tokens: &mut Vec<Token>) -> Result<()> {
+        tokens.extend(self.peeked.take());
+        self.skip_shebang();
         while !self.is_at_end() {
-            self.start = self.current;
-            if let Some(token) = self.scan_token()? {
+            if let Some(token) = self.next_lexeme()? {
                 tokens.push(token);
             }
         }
+        tokens.push(self.eof());
+        Ok(())
+    }
 
-        tokens.push(Token {
+    /// Scans the whole source like [`scan_tokens`](Self::scan_tokens), but
+    /// carries on after an error instead of stopping, and returns every
+    /// error along with the tokens that did scan.
+    ///
+    /// After an unexpected character, scanning resumes with the next one.
+    /// After a bad escape sequence, the rest of the string is skipped so its
+    /// closing quote does not open a new one.
+    pub fn all_errors(mut self) -> (Vec<Token>, Vec<Error>) {
+        let mut tokens: Vec<Token> = self.peeked.take().into_iter().collect();
+        let mut errors = vec![];
+        self.skip_shebang();
+        while !self.is_at_end() {
+            match self.next_lexeme() {
+                Ok(token) => tokens.extend(token),
+                Err(e) => {
+                    errors.push(e);
+                    if self.source[self.start..].starts_with('"') {
+                        self.skip_string();
+                    }
+                }
+            }
+        }
+        tokens.push(self.eof());
+        (tokens, errors)
+    }
+
+    /// Skips past the closing quote of a string that failed to scan.
+    fn skip_string(&mut self) {
+        let mut escaped = false;
+        while let Some(c) = self.advance() {
+            if c == '\n' {
+                self.newline();
+            }
+            if c == '"' && !escaped {
+                break;
+            }
+            escaped = c == '\\' && !escaped;
+        }
+    }
+
+    /// Replaces lines `line_start..=line_end` (1-based) of the source with
+    /// `new_text` and returns the tokens of the edited source.
+    ///
+    /// Only the edited lines are scanned again, starting from the first
+    /// token that reaches into them. Scanning stops as soon as it lines up
+    /// with a token from the previous scan past the edit, and the remaining
+    /// tokens are reused with their positions shifted. Without a previous
+    /// successful scan, the whole source is scanned.
+    ///
+    /// An empty range (`line_end == line_start - 1`) inserts `new_text`
+    /// before `line_start`. A range that does not lie within the source's
+    /// lines fails with [`Error::LineRange`], leaving the source as it was.
+    pub fn rescan_range(
+        &mut self,
+        line_start: usize,
+        line_end: usize,
+        new_text: &str,
+    ) -> Result<Vec<Token>> {
+        let line_count = self.source.split('\n').count();
+        if line_start < 1 || line_start > line_end + 1 || line_end > line_count {
+            return Err(Error::LineRange {
+                start: line_start,
+                end: line_end,
+                lines: line_count,
+            });
+        }
+        let edit_start = self.line_offset(line_start);
+        let edit_end = self.line_offset(line_end + 1);
+        let removed_lines = self.source[edit_start..edit_end].matches('\n').count();
+        self.source.replace_range(edit_start..edit_end, new_text);
+        self.peeked = None;
+
+        let old = std::mem::take(&mut self.tokens);
+        if old.is_empty() {
+            self.reset();
+            return self.scan_tokens();
+        }
+        let new_edit_end = edit_start + new_text.len();
+        let line_delta = new_text.matches('\n').count() as isize - removed_lines as isize;
+
+        // Keep every token that ends before the edit, but never the EOF token.
+        let keep = old
+            .partition_point(|token| token.end <= edit_start)
+            .min(old.len() - 1);
+        let restart = old[keep].start.min(edit_start);
+        let suffix = old.partition_point(|token| token.start < edit_end);
+
+        self.current = restart;
+        self.line = line_start - self.source[restart..edit_start].matches('\n').count();
+        self.line_start = self.line_offset_of(restart);
+
+        let mut tokens = old[..keep].to_vec();
+        loop {
+            if self.current >= new_edit_end {
+                let old_position = self.current + edit_end - new_edit_end;
+                if let Ok(i) = old[suffix..].binary_search_by_key(&old_position, |t| t.start) {
+                    let first_line_end = self.source[new_edit_end..]
+                        .find('\n')
+                        .map_or(self.source.len(), |i| new_edit_end + i);
+                    for token in &old[suffix + i..] {
+                        tokens.push(self.shifted(token, new_edit_end, edit_end, line_delta));
+                        let token = tokens.last_mut().expect("Lost shifted token");
+                        if token.start <= first_line_end {
+                            token.column = token.start - self.line_offset_of(token.start) + 1;
+                        }
+                    }
+                    break;
+                }
+            }
+            if self.is_at_end() {
+                tokens.push(self.eof());
+                break;
+            }
+            self.skip_shebang();
+            if let Some(token) = self.next_lexeme()? {
+                tokens.push(token);
+            }
+        }
+        self.tokens = tokens.clone();
+        Ok(tokens)
+    }
+
+    /// Moves a token from the previous scan to its place after an edit that
+    /// turned `old_end` into `new_end`.
+    fn shifted(&self, token: &Token, new_end: usize, old_end: usize, line_delta: isize) -> Token {
+        Token {
+            start: token.start + new_end - old_end,
+            end: token.end + new_end - old_end,
+            line: token.line.saturating_add_signed(line_delta),
+            ..token.clone()
+        }
+    }
+
+    /// The byte offset where 1-based `line` begins, or the end of the source
+    /// for the line after the last.
+    fn line_offset(&self, line: usize) -> usize {
+        if line == 1 {
+            return 0;
+        }
+        self.source
+            .match_indices('\n')
+            .nth(line - 2)
+            .map_or(self.source.len(), |(i, _)| i + 1)
+    }
+
+    /// The byte offset where the line containing `offset` begins.
+    fn line_offset_of(&self, offset: usize) -> usize {
+        self.source[..offset].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn eof(&self) -> Token {
+        Token {
             ty: TokenType::Eof,
             lexeme: String::new(),
             line: self.line,
-        });
-        Ok(tokens)
+            column: self.current - self.line_start + 1,
+            start: self.current,
+            end: self.current,
+            source_name: self.source_name.clone(),
+        }
+    }
+
+    /// Scans one lexeme, which is `None` for whitespace and comments.
+    fn next_lexeme(&mut self) -> Result<Option<Token>> {
+        self.start = self.current;
+        self.column = self.start - self.line_start + 1;
+        self.scan_token()
     }
 
     fn scan_token(&mut self) -> Result<Option<Token>> {
@@ -190,13 +711,14 @@ impl Scanner {
             }
             ' ' | '\r' | '\t' => return SKIP_TOKEN,
             '\n' => {
-                self.line += 1;
+                self.newline();
                 return SKIP_TOKEN;
             }
+            '"' if self.remaining().starts_with("\"\"") => self.raw_string(),
             '"' => self.string(),
             c if c.is_ascii_digit() => self.number(),
             c if c.is_alphabetic() || c == '_' => self.identifier(),
-            _ => Err(Error::new(self.line, "Unexpected character.")),
+            _ => Err(self.error("Unexpected character.")),
         };
         Some(token).transpose()
     }
@@ -207,16 +729,50 @@ impl Scanner {
             ty,
             lexeme,
             line: self.line,
+            column: self.column,
+            start: self.start,
+            end: self.current,
+            source_name: self.source_name.clone(),
         })
     }
 
+    /// An error on the current line. If the token started on an earlier
+    /// line, the column is where scanning stopped instead of where it began.
+    fn error(&self, message: &'static str) -> Error {
+        let column = if self.line_start > self.start {
+            self.current - self.line_start + 1
+        } else {
+            self.column
+        };
+        Error::Scan {
+            file: self.file(),
+            line: self.line,
+            column,
+            message,
+        }
+    }
+
+    /// A string still open at the end of the source, which more input could
+    /// close.
+    fn unterminated(&self) -> Error {
+        Error::UnexpectedEof {
+            file: self.file(),
+            line: self.line,
+            message: "Unterminated string.",
+        }
+    }
+
+    fn file(&self) -> Option<PathBuf> {
+        self.source_name.as_deref().map(Path::to_path_buf)
+    }
+
     fn matches(&mut self, expected: char) -> bool {
         if self.is_at_end() {
             return false;
         }
-        match self.source.chars().nth(self.current) {
+        match self.remaining().chars().next() {
             Some(c) if c == expected => {
-                self.current += 1;
+                self.current += c.len_utf8();
                 true
             }
             _ => false,
@@ -224,9 +780,14 @@ impl Scanner {
     }
 
     fn advance(&mut self) -> Option<char> {
-        let c = self.source.chars().nth(self.current);
-        self.current += 1;
-        c
+        let c = self.remaining().chars().next()?;
+        self.current += c.len_utf8();
+        Some(c)
+    }
+
+    fn newline(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
     }
 
     fn is_at_end(&self) -> bool {
@@ -234,31 +795,97 @@ impl Scanner {
     }
 
     fn peek(&self) -> char {
-        self.source.chars().nth(self.current).unwrap_or('\0')
+        self.remaining().chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        self.source.chars().nth(self.current + 1).unwrap_or('\0')
+        self.remaining().chars().nth(1).unwrap_or('\0')
+    }
+
+    /// The unscanned part of the source. `current` is a byte offset, so it
+    /// always sits on a character boundary.
+    fn remaining(&self) -> &str {
+        &self.source[self.current..]
     }
 
     fn string(&mut self) -> Result<Token> {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+        let mut literal = String::new();
+        loop {
+            match self.advance() {
+                None => return Err(self.unterminated()),
+                Some('"') => break,
+                Some('\\') => literal.push(self.escape()?),
+                Some(c) => {
+                    if c == '\n' {
+                        self.newline();
+                    }
+                    literal.push(c);
+                }
             }
-            let _ = self.advance();
         }
+        self.token(TokenType::String(literal))
+    }
 
-        if self.is_at_end() {
-            return Err(Error::new(self.line, "Unterminated string."));
+    /// Scans a `"""..."""` string. The content is taken literally, without
+    /// decoding escapes, and a newline directly after the opening quotes is
+    /// dropped.
+    fn raw_string(&mut self) -> Result<Token> {
+        self.current += 2;
+        if self.matches('\n') {
+            self.newline();
+        }
+        let content_start = self.current;
+        while !self.remaining().starts_with("\"\"\"") {
+            match self.advance() {
+                None => return Err(self.unterminated()),
+                Some('\n') => self.newline(),
+                Some(_) => {}
+            }
+        }
+        let literal = self.source[content_start..self.current].to_string();
+        self.current += 3;
+        self.token(TokenType::RawString(literal))
+    }
+
+    /// Decodes the escape sequence following a backslash in a string.
+    fn escape(&mut self) -> Result<char> {
+        let c = match self.advance() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('u') => return self.unicode_escape(),
+            None => return Err(self.unterminated()),
+            Some(_) => return Err(self.error("Invalid escape sequence.")),
+        };
+        Ok(c)
+    }
+
+    /// Decodes `\u{H...}` (one to six hex digits) or `\uHHHH`.
+    fn unicode_escape(&mut self) -> Result<char> {
+        let braced = self.matches('{');
+        let start = self.current;
+        let max_digits = if braced { 6 } else { 4 };
+        while self.current - start < max_digits && self.peek().is_ascii_hexdigit() {
+            let _ = self.advance();
         }
+        let end = self.current;
 
-        // The closing quote
-        let _ = self.advance();
+        let well_formed = if braced {
+            end > start && self.matches('}')
+        } else {
+            end - start == 4
+        };
+        if !well_formed {
+            return Err(self.error("Invalid Unicode escape."));
+        }
 
-        let lexeme = self.current_lexeme();
-        let literal = lexeme[1..(lexeme.len() - 1)].to_string();
-        self.token(TokenType::String(literal))
+        u32::from_str_radix(&self.source[start..end], 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("Invalid Unicode code point."))
     }
 
     fn number(&mut self) -> Result<Token> {
@@ -276,7 +903,7 @@ impl Scanner {
         let number: f64 = self
             .current_lexeme()
             .parse()
-            .map_err(|_| Error::new(self.line, "Invalid number literal."))?;
+            .map_err(|_| self.error("Invalid number literal."))?;
         self.token(TokenType::Number(number))
     }
 
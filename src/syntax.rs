@@ -1,39 +1,658 @@
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+
 use crate::error::*;
-use crate::scanner::{Token, TokenType};
+use crate::scanner::{Scanner, Token, TokenType};
 
-#[derive(Debug)]
+/// An expression node. Variant names follow the book's `Expr` classes, so
+/// `Expr::Binary` here is `Expr.Binary` there.
+#[derive(Debug, Clone)]
 pub enum Expr {
+    /// `left operator right`, for the `equality` through `factor` rules of
+    /// chapter 6, "Parsing Expressions".
     Binary {
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    /// `"(" expression ")"`, from `primary` (chapter 6).
     Grouping(Box<Expr>),
+    /// A `NUMBER`, `STRING`, `true`, `false` or `nil` token, from `primary`
+    /// (chapter 6).
     Literal(Token),
-    Unary {
+    /// `( "!" | "-" ) unary`, from `unary` (chapter 6).
+    Unary { operator: Token, right: Box<Expr> },
+    /// A read of `IDENTIFIER`, added to `primary` in chapter 8, "Statements
+    /// and State".
+    Variable(Token),
+    /// `IDENTIFIER "=" assignment`, from `assignment` (chapter 8).
+    Assign { name: Token, value: Box<Expr> },
+    /// `left ( "and" | "or" ) right`, from `logic_or` and `logic_and` in
+    /// chapter 9, "Control Flow". Kept apart from `Binary` because the right
+    /// operand may not be evaluated.
+    Logical {
+        left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    /// `expression ( "," expression )*` inside parentheses. Not part of the
+    /// book's grammar; it is the comma operator from chapter 6's challenges.
+    Sequence { left: Box<Expr>, right: Box<Expr> },
+    /// `primary ( "(" arguments? ")" )*`, from `call` in chapter 10,
+    /// "Functions". `paren` is the closing parenthesis, used to report
+    /// errors.
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>,
+    },
+    /// `call "." IDENTIFIER`, a property read from `call` in chapter 12,
+    /// "Classes". Without classes, only methods of built-in types have
+    /// names to read.
+    Get { object: Box<Expr>, name: Token },
+    /// `unary "as" IDENTIFIER`, converting a value to the type `number`,
+    /// `string` or `bool`. Not part of the book's grammar.
+    Cast { expr: Box<Expr>, target_type: Token },
+    /// `"fun" "(" parameters? ")" block`, an anonymous function from
+    /// `primary`. Not part of the book's grammar; it is chapter 10's second
+    /// challenge.
+    Lambda(Rc<Function>),
+}
+
+impl Expr {
+    /// A line the expression is on, for reporting errors about its value.
+    pub fn line(&self) -> usize {
+        self.token().line
+    }
+
+    /// The token that [`line`](Self::line) comes from, which also names the
+    /// source the expression was parsed from.
+    pub(crate) fn token(&self) -> &Token {
+        match self {
+            Expr::Binary { operator, .. }
+            | Expr::Unary { operator, .. }
+            | Expr::Logical { operator, .. } => operator,
+            Expr::Grouping(expr) => expr.token(),
+            Expr::Literal(token) | Expr::Variable(token) => token,
+            Expr::Assign { name, .. } | Expr::Get { name, .. } => name,
+            Expr::Sequence { right, .. } => right.token(),
+            Expr::Call { paren, .. } => paren,
+            Expr::Cast { target_type, .. } => target_type,
+            Expr::Lambda(function) => &function.name,
+        }
+    }
+}
+
+/// A statement node. Cloning shares function declarations rather than
+/// copying their bodies.
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Block(Vec<Stmt>),
+    /// `export` before a `var` or `fun` declaration, which is the only kind
+    /// of statement it wraps.
+    Export(Box<Stmt>),
+    /// `export default expression;`, imported under the module's file name.
+    ExportDefault(Expr),
+    Expression(Expr),
+    Function(Rc<Function>),
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    /// `import "path";`, where `path` is the string literal naming the file.
+    Import {
+        path: Token,
+    },
+    Print(Expr),
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    Var {
+        name: Token,
+        initializer: Option<Expr>,
+    },
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+    },
+    /// `with (x = 1, y = 2) body`, which runs `body` in a new scope holding
+    /// the bindings. Every initializer is evaluated in the enclosing scope.
+    With {
+        bindings: Vec<(Token, Expr)>,
+        body: Box<Stmt>,
+    },
+}
+
+/// A function declaration, shared between the syntax tree and every
+/// closure created from it.
+#[derive(Debug)]
+pub struct Function {
+    /// The declared name, or the `fun` keyword of an anonymous function.
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Stmt>,
+}
+
+/// Prints the expression as an S-expression, like the book's `AstPrinter`.
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => write!(f, "({} {left} {right})", operator.lexeme),
+            Expr::Grouping(expr) => write!(f, "(group {expr})"),
+            Expr::Literal(token) => write!(f, "{}", token.lexeme),
+            Expr::Unary { operator, right } => write!(f, "({} {right})", operator.lexeme),
+            Expr::Variable(name) => write!(f, "{}", name.lexeme),
+            Expr::Assign { name, value } => write!(f, "(= {} {value})", name.lexeme),
+            Expr::Logical {
+                left,
+                operator,
+                right,
+            } => write!(f, "({} {left} {right})", operator.lexeme),
+            Expr::Sequence { left, right } => write!(f, "(, {left} {right})"),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                write!(f, "(call {callee}")?;
+                for argument in arguments {
+                    write!(f, " {argument}")?;
+                }
+                write!(f, ")")
+            }
+            Expr::Get { object, name } => write!(f, "(. {object} {})", name.lexeme),
+            Expr::Cast { expr, target_type } => write!(f, "(as {expr} {})", target_type.lexeme),
+            Expr::Lambda(function) => {
+                write!(f, "(fun (")?;
+                for (i, param) in function.params.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { " " };
+                    write!(f, "{separator}{}", param.lexeme)?;
+                }
+                write!(f, "))")
+            }
+        }
+    }
 }
 
+impl FromStr for Expr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = Scanner::new(s.to_string()).scan_tokens()?;
+        Parser::new(tokens).parse_expression()
+    }
+}
+
+/// The most parameters a function can declare, or arguments a call can pass.
+const MAX_ARITY: usize = 255;
+
+/// A recursive descent parser with one method per rule of the
+/// [grammar](crate::grammar).
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    reporter: Option<Box<dyn Fn(Error)>>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            reporter: None,
+        }
+    }
+
+    /// Hands each syntax error to `reporter` as soon as it is found, instead
+    /// of stopping at the first one. The parser then skips ahead to the next
+    /// statement and carries on.
+    pub fn with_error_reporter(mut self, reporter: impl Fn(Error) + 'static) -> Self {
+        self.reporter = Some(Box::new(reporter));
+        self
+    }
+
+    /// Parses every statement, failing on the first syntax error.
+    ///
+    /// With an [error reporter](Self::with_error_reporter) this never fails,
+    /// and only the statements that parsed cleanly are returned. They should
+    /// not be run if anything was reported.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>> {
+        let mut statements = vec![];
+        while !self.is_at_end() {
+            let start = self.current;
+            let declaration = if self.matches(&[TokenType::Export]) {
+                self.export_declaration()
+            } else {
+                self.declaration()
+            };
+            match declaration {
+                Ok(statement) => statements.push(statement),
+                Err(e) => match &self.reporter {
+                    Some(reporter) => {
+                        reporter(e);
+                        self.synchronize(start);
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
+        Ok(statements)
+    }
+
+    /// Parses the tokens as a single expression, with nothing following it.
+    pub fn parse_expression(&mut self) -> Result<Expr> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(self.error("Expected end of expression."));
+        }
+        Ok(expr)
+    }
+
+    /// Every token the parser was given, including those already consumed.
+    /// Index it with [`current_position`](Self::current_position) to find the
+    /// token the parser is looking at.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// The tokens not yet consumed, starting with the one the parser is
+    /// looking at. After a failed parse this begins at the offending token.
+    pub fn remaining_tokens(&self) -> &[Token] {
+        &self.tokens[self.current..]
+    }
+
+    /// The index of the next token to be consumed.
+    pub fn current_position(&self) -> usize {
+        self.current
+    }
+
+    /// `exportDecl → "export" ( funDecl | varDecl | "default" expression ";" ) ;`
+    ///
+    /// `default` is only special here, and is otherwise an identifier.
+    fn export_declaration(&mut self) -> Result<Stmt> {
+        if self.matches(&[TokenType::Fun]) {
+            Ok(Stmt::Export(Box::new(self.function()?)))
+        } else if self.matches(&[TokenType::Var]) {
+            Ok(Stmt::Export(Box::new(self.var_declaration()?)))
+        } else if self.peek().is_some_and(|token| token.lexeme == "default") {
+            self.advance();
+            let value = self.expression()?;
+            self.consume(&TokenType::Semicolon, "Expected ';' after default export.")?;
+            Ok(Stmt::ExportDefault(value))
+        } else {
+            Err(self.error("Expected 'fun', 'var' or 'default' after 'export'."))
+        }
+    }
+
+    /// `declaration → funDecl | importDecl | varDecl | statement ;`
+    fn declaration(&mut self) -> Result<Stmt> {
+        if self.check(&TokenType::Export) {
+            return Err(self.error("Can only export from the top level."));
+        }
+        // `fun (` begins an anonymous function in an expression statement.
+        if self.check(&TokenType::Fun) && !self.check_next(&TokenType::LeftParen) {
+            self.advance();
+            self.function()
+        } else if self.matches(&[TokenType::Import]) {
+            self.import_declaration()
+        } else if self.matches(&[TokenType::Var]) {
+            self.var_declaration()
+        } else {
+            self.statement()
+        }
     }
 
-    pub fn parse(mut self) -> Result<Expr> {
-        self.expression()
+    /// `funDecl → "fun" IDENTIFIER "(" parameters? ")" block ;`
+    fn function(&mut self) -> Result<Stmt> {
+        let name = self.consume(
+            &TokenType::Identifier(String::new()),
+            "Expected function name.",
+        )?;
+        self.consume(&TokenType::LeftParen, "Expected '(' after function name.")?;
+        Ok(Stmt::Function(self.function_rest(name)?))
     }
 
+    /// `"fun" "(" parameters? ")" block`, once `fun` is consumed.
+    fn lambda(&mut self) -> Result<Expr> {
+        let keyword = self.previous().cloned().expect("Lost 'fun' after matching");
+        self.consume(&TokenType::LeftParen, "Expected '(' after 'fun'.")?;
+        Ok(Expr::Lambda(self.function_rest(keyword)?))
+    }
+
+    /// The parameters and body of a function, after its opening parenthesis.
+    fn function_rest(&mut self, name: Token) -> Result<Rc<Function>> {
+        let mut params = vec![];
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARITY {
+                    return Err(self.error("Can't have more than 255 parameters."));
+                }
+                params.push(self.consume(
+                    &TokenType::Identifier(String::new()),
+                    "Expected parameter name.",
+                )?);
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(&TokenType::RightParen, "Expected ')' after parameters.")?;
+        self.consume(&TokenType::LeftBrace, "Expected '{' before function body.")?;
+        let body = self.block()?;
+        Ok(Rc::new(Function { name, params, body }))
+    }
+
+    /// `importDecl → "import" STRING ";" ;`
+    fn import_declaration(&mut self) -> Result<Stmt> {
+        let path = self.consume(
+            &TokenType::String(String::new()),
+            "Expected module path after 'import'.",
+        )?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after module path.")?;
+        Ok(Stmt::Import { path })
+    }
+
+    /// `varDecl → "var" IDENTIFIER ( "=" expression )? ";" ;`
+    fn var_declaration(&mut self) -> Result<Stmt> {
+        let name = self.consume(
+            &TokenType::Identifier(String::new()),
+            "Expected variable name.",
+        )?;
+        let initializer = if self.matches(&[TokenType::Equal]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(
+            &TokenType::Semicolon,
+            "Expected ';' after variable declaration.",
+        )?;
+        Ok(Stmt::Var { name, initializer })
+    }
+
+    /// `statement → exprStmt | forStmt | ifStmt | printStmt | returnStmt`
+    /// `          | whileStmt | withStmt | block ;`
+    ///
+    /// Declarations are not statements, so `if (x) var y = 1;` is rejected.
+    fn statement(&mut self) -> Result<Stmt> {
+        if self.matches(&[TokenType::For]) {
+            self.for_statement()
+        } else if self.matches(&[TokenType::If]) {
+            self.if_statement()
+        } else if self.matches(&[TokenType::While]) {
+            self.while_statement()
+        } else if self.at_with_statement() {
+            self.advance();
+            self.with_statement()
+        } else if self.matches(&[TokenType::Print]) {
+            self.print_statement()
+        } else if self.matches(&[TokenType::Return]) {
+            self.return_statement()
+        } else if self.matches(&[TokenType::LeftBrace]) {
+            Ok(Stmt::Block(self.block()?))
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    /// `forStmt → "for" "(" ( varDecl | exprStmt | ";" ) expression? ";"`
+    /// `          expression? ")" statement ;`
+    ///
+    /// Desugared into a `while` loop inside a block.
+    fn for_statement(&mut self) -> Result<Stmt> {
+        self.consume(&TokenType::LeftParen, "Expected '(' after 'for'.")?;
+        let initializer = if self.matches(&[TokenType::Semicolon]) {
+            None
+        } else if self.matches(&[TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+        let condition = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        let semicolon =
+            self.consume(&TokenType::Semicolon, "Expected ';' after loop condition.")?;
+        let increment = if self.check(&TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(&TokenType::RightParen, "Expected ')' after for clauses.")?;
+
+        let mut body = self.statement()?;
+        if let Some(increment) = increment {
+            body = Stmt::Block(vec![body, Stmt::Expression(increment)]);
+        }
+        let condition = condition.unwrap_or_else(|| {
+            Expr::Literal(Token {
+                ty: TokenType::True,
+                lexeme: "true".to_string(),
+                ..semicolon
+            })
+        });
+        body = Stmt::While {
+            condition,
+            body: Box::new(body),
+        };
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(vec![initializer, body]);
+        }
+        Ok(body)
+    }
+
+    /// `ifStmt → "if" "(" expression ")" statement ( "else" statement )? ;`
+    fn if_statement(&mut self) -> Result<Stmt> {
+        self.consume(&TokenType::LeftParen, "Expected '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(&TokenType::RightParen, "Expected ')' after if condition.")?;
+        let then_branch = Box::new(self.statement()?);
+        let else_branch = if self.matches(&[TokenType::Else]) {
+            Some(Box::new(self.statement()?))
+        } else {
+            None
+        };
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    /// `whileStmt → "while" "(" expression ")" statement ;`
+    fn while_statement(&mut self) -> Result<Stmt> {
+        self.consume(&TokenType::LeftParen, "Expected '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(&TokenType::RightParen, "Expected ')' after condition.")?;
+        let body = Box::new(self.statement()?);
+        Ok(Stmt::While { condition, body })
+    }
+
+    /// Whether the next tokens are `with ( IDENTIFIER =` and what follows
+    /// the matching `)` cannot continue an expression, which starts a
+    /// `withStmt`. `with` is otherwise an identifier, so `with(x);` and
+    /// `with(x = 1);` still call a function named `with`. A body that
+    /// starts with an operator, such as `-x;` or `(x);`, is read as
+    /// continuing that call too.
+    fn at_with_statement(&self) -> bool {
+        let is = |offset: usize, ty: &TokenType| {
+            self.tokens
+                .get(self.current + offset)
+                .is_some_and(|token| token.ty.matches(ty))
+        };
+        let starts_with = self.peek().is_some_and(
+            |token| matches!(&token.ty, TokenType::Identifier(name) if name == "with"),
+        ) && is(1, &TokenType::LeftParen)
+            && is(2, &TokenType::Identifier(String::new()))
+            && is(3, &TokenType::Equal);
+        if !starts_with {
+            return false;
+        }
+        let mut depth = 0;
+        let mut after = self.tokens[self.current + 1..].iter();
+        for token in after.by_ref() {
+            match token.ty {
+                TokenType::LeftParen => depth += 1,
+                TokenType::RightParen => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+        }
+        // Without a closing `)` this is an unfinished `withStmt`, which
+        // reports the missing `)`.
+        match after.next().map(|token| &token.ty) {
+            None => depth != 0,
+            Some(TokenType::Semicolon | TokenType::Eof) => false,
+            Some(ty) => PrattParser::rule(ty).infix.is_none(),
+        }
+    }
+
+    /// `withStmt → "with" "(" binding ( "," binding )* ")" statement ;`
+    /// `binding  → IDENTIFIER "=" expression ;`
+    fn with_statement(&mut self) -> Result<Stmt> {
+        self.consume(&TokenType::LeftParen, "Expected '(' after 'with'.")?;
+        let mut bindings = Vec::new();
+        loop {
+            let name = self.consume(
+                &TokenType::Identifier(String::new()),
+                "Expected variable name.",
+            )?;
+            self.consume(&TokenType::Equal, "Expected '=' after variable name.")?;
+            bindings.push((name, self.expression()?));
+            if !self.matches(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(&TokenType::RightParen, "Expected ')' after bindings.")?;
+        let body = Box::new(self.statement()?);
+        Ok(Stmt::With { bindings, body })
+    }
+
+    /// `printStmt → "print" expression ";" ;`
+    fn print_statement(&mut self) -> Result<Stmt> {
+        let value = self.expression()?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after value.")?;
+        Ok(Stmt::Print(value))
+    }
+
+    /// `returnStmt → "return" expression? ";" ;`
+    fn return_statement(&mut self) -> Result<Stmt> {
+        let keyword = self
+            .previous()
+            .cloned()
+            .expect("Lost return keyword after matching");
+        let value = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(&TokenType::Semicolon, "Expected ';' after return value.")?;
+        Ok(Stmt::Return { keyword, value })
+    }
+
+    /// `exprStmt → expression ";" ;`
+    fn expression_statement(&mut self) -> Result<Stmt> {
+        let expr = self.expression()?;
+        self.consume(&TokenType::Semicolon, "Expected ';' after expression.")?;
+        Ok(Stmt::Expression(expr))
+    }
+
+    /// `block → "{" declaration* "}" ;`
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        let mut statements = vec![];
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.declaration()?);
+        }
+        self.consume(&TokenType::RightBrace, "Expected '}' after block.")?;
+        Ok(statements)
+    }
+
+    /// `expression → assignment ;`
     fn expression(&mut self) -> Result<Expr> {
-        self.equality()
+        self.assignment()
+    }
+
+    /// `sequence → expression ( "," expression )* ;`
+    ///
+    /// Comma-separated expressions, which are only allowed inside
+    /// parentheses.
+    fn sequence(&mut self) -> Result<Expr> {
+        let mut expr = self.expression()?;
+        while self.matches(&[TokenType::Comma]) {
+            let right = Box::new(self.expression()?);
+            expr = Expr::Sequence {
+                left: Box::new(expr),
+                right,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// `assignment → IDENTIFIER "=" assignment | logic_or ;`
+    fn assignment(&mut self) -> Result<Expr> {
+        let expr = self.or()?;
+
+        if self.matches(&[TokenType::Equal]) {
+            let equals = self.previous().cloned();
+            let value = Box::new(self.assignment()?);
+            return match expr {
+                Expr::Variable(name) => Ok(Expr::Assign { name, value }),
+                _ => Err(Error::Syntax {
+                    file: equals.as_ref().and_then(Token::file),
+                    line: equals.map(|t| t.line).unwrap_or_default(),
+                    message: "Invalid assignment target.",
+                }),
+            };
+        }
+        Ok(expr)
     }
 
+    /// `logic_or → logic_and ( "or" logic_and )* ;`
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.matches(&[TokenType::Or]) {
+            let operator = self
+                .previous()
+                .cloned()
+                .expect("Lost logical operator after matching");
+            let right = Box::new(self.and()?);
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// `logic_and → equality ( "and" equality )* ;`
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.equality()?;
+        while self.matches(&[TokenType::And]) {
+            let operator = self
+                .previous()
+                .cloned()
+                .expect("Lost logical operator after matching");
+            let right = Box::new(self.equality()?);
+            expr = Expr::Logical {
+                left: Box::new(expr),
+                operator,
+                right,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// `equality → comparison ( ( "!=" | "==" ) comparison )* ;`
     fn equality(&mut self) -> Result<Expr> {
         let mut expr = self.comparison()?;
         while self.matches(&[TokenType::BangEqual, TokenType::EqualEqual]) {
@@ -52,6 +671,7 @@ impl Parser {
         Ok(expr)
     }
 
+    /// `comparison → term ( ( ">" | ">=" | "<" | "<=" ) term )* ;`
     fn comparison(&mut self) -> Result<Expr> {
         let mut expr = self.term()?;
 
@@ -76,6 +696,7 @@ impl Parser {
         Ok(expr)
     }
 
+    /// `term → factor ( ( "-" | "+" ) factor )* ;`
     fn term(&mut self) -> Result<Expr> {
         let mut expr = self.factor()?;
 
@@ -95,8 +716,9 @@ impl Parser {
         Ok(expr)
     }
 
+    /// `factor → cast ( ( "/" | "*" ) cast )* ;`
     fn factor(&mut self) -> Result<Expr> {
-        let mut expr = self.unary()?;
+        let mut expr = self.cast()?;
 
         while self.matches(&[TokenType::Slash, TokenType::Star]) {
             let left = Box::new(expr);
@@ -104,7 +726,7 @@ impl Parser {
                 .previous()
                 .cloned()
                 .expect("Lost multiplicative operator token after matching");
-            let right = Box::new(self.unary()?);
+            let right = Box::new(self.cast()?);
             expr = Expr::Binary {
                 left,
                 operator,
@@ -114,6 +736,42 @@ impl Parser {
         Ok(expr)
     }
 
+    /// `cast → unary ( "as" IDENTIFIER )* ;`
+    ///
+    /// `as` is only special here, and is otherwise an identifier.
+    fn cast(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self
+            .peek()
+            .is_some_and(|token| matches!(&token.ty, TokenType::Identifier(name) if name == "as"))
+        {
+            self.advance();
+            let target_type = self.type_name()?;
+            expr = Expr::Cast {
+                expr: Box::new(expr),
+                target_type,
+            };
+        }
+        Ok(expr)
+    }
+
+    /// The type named after `as`.
+    fn type_name(&mut self) -> Result<Token> {
+        match self.peek() {
+            Some(token)
+                if token.ty.matches(&TokenType::Identifier(String::new()))
+                    && matches!(token.lexeme.as_str(), "number" | "string" | "bool") =>
+            {
+                Ok(self
+                    .advance()
+                    .cloned()
+                    .expect("Lost type name after matching"))
+            }
+            _ => Err(self.error("Expected 'number', 'string' or 'bool' after 'as'.")),
+        }
+    }
+
+    /// `unary → ( "!" | "-" ) unary | call ;`
     fn unary(&mut self) -> Result<Expr> {
         if self.matches(&[TokenType::Bang, TokenType::Minus]) {
             let operator = self
@@ -123,10 +781,56 @@ impl Parser {
             let right = Box::new(self.unary()?);
             Ok(Expr::Unary { operator, right })
         } else {
-            self.primary()
+            self.call()
+        }
+    }
+
+    /// `call → primary ( "(" arguments? ")" | "." IDENTIFIER )* ;`
+    fn call(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.matches(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.matches(&[TokenType::Dot]) {
+                let name = self.consume(
+                    &TokenType::Identifier(String::new()),
+                    "Expected property name after '.'.",
+                )?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else {
+                break;
+            }
         }
+        Ok(expr)
+    }
+
+    /// `arguments → expression ( "," expression )* ;`
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr> {
+        let mut arguments = vec![];
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARITY {
+                    return Err(self.error("Can't have more than 255 arguments."));
+                }
+                arguments.push(self.expression()?);
+                if !self.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(&TokenType::RightParen, "Expected ')' after arguments.")?;
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
     }
 
+    /// `primary → "true" | "false" | "nil" | NUMBER | STRING | IDENTIFIER`
+    /// `        | "(" sequence ")" | "fun" "(" parameters? ")" block ;`
     fn primary(&mut self) -> Result<Expr> {
         if self.matches(&[
             TokenType::False,
@@ -134,36 +838,58 @@ impl Parser {
             TokenType::Nil,
             TokenType::Number(0.),
             TokenType::String("".to_string()),
+            TokenType::RawString("".to_string()),
         ]) {
             Ok(Expr::Literal(
                 self.previous()
                     .cloned()
                     .expect("Lost literal after matching"),
             ))
+        } else if self.matches(&[TokenType::Identifier(String::new())]) {
+            Ok(Expr::Variable(
+                self.previous()
+                    .cloned()
+                    .expect("Lost identifier after matching"),
+            ))
         } else if self.matches(&[TokenType::LeftParen]) {
-            let expr = self.expression()?;
+            let expr = self.sequence()?;
             self.consume(&TokenType::RightParen, "Expected ')' after expression.")?;
             Ok(Expr::Grouping(Box::new(expr)))
+        } else if self.check(&TokenType::Fun) && self.check_next(&TokenType::LeftParen) {
+            self.advance();
+            self.lambda()
         } else {
-            Err(Error::Syntax {
-                line: self.peek().map(|t| t.line).unwrap_or_default(),
-                message: "Expected expression.",
-            })
+            Err(self.error("Expected expression."))
         }
     }
 
-    fn synchronize(&mut self) {
-        self.advance();
+    /// Skips to the likely start of the next statement after an error: just
+    /// past a `;`, or at a keyword that begins a statement, as in chapter 6's
+    /// "Synchronizing a recursive descent parser".
+    ///
+    /// Unlike the book, the token the error was reported at is kept if it
+    /// already begins a statement, so `print 1 var x = 2;` loses only the
+    /// `print`. It is skipped only when the failed declaration, which began
+    /// at `start`, consumed nothing, so that parsing always moves forward.
+    fn synchronize(&mut self, start: usize) {
+        if self.current == start {
+            self.advance();
+        }
         while !self.is_at_end() {
-            if self.previous().unwrap().ty.matches(&TokenType::Semicolon) {
+            if self
+                .previous()
+                .is_some_and(|t| t.ty.matches(&TokenType::Semicolon))
+            {
                 return;
             }
 
             match self.peek().map(|t| &t.ty) {
                 Some(TokenType::Class)
+                | Some(TokenType::Export)
                 | Some(TokenType::For)
                 | Some(TokenType::Fun)
                 | Some(TokenType::If)
+                | Some(TokenType::Import)
                 | Some(TokenType::Print)
                 | Some(TokenType::Return)
                 | Some(TokenType::Var)
@@ -175,15 +901,30 @@ impl Parser {
         }
     }
 
-    fn consume(&mut self, ty: &TokenType, message: &'static str) -> Result<()> {
+    /// An error at the next token, naming it. Running out of tokens is
+    /// reported as [`Error::UnexpectedEof`], since more input could fix it.
+    fn error(&self, message: &'static str) -> Error {
+        match self.peek() {
+            Some(token) if !self.is_at_end() => Error::UnexpectedToken {
+                file: token.file(),
+                line: token.line,
+                column: token.column,
+                found: token.ty.display_name(),
+                message,
+            },
+            token => Error::UnexpectedEof {
+                file: token.and_then(Token::file),
+                line: token.map(|t| t.line).unwrap_or_default(),
+                message,
+            },
+        }
+    }
+
+    fn consume(&mut self, ty: &TokenType, message: &'static str) -> Result<Token> {
         if self.check(ty) {
-            self.advance();
-            Ok(())
+            Ok(self.advance().cloned().expect("Lost token after consuming"))
         } else {
-            Err(Error::Syntax {
-                line: self.peek().map(|t| t.line).unwrap_or_default(),
-                message,
-            })
+            Err(self.error(message))
         }
     }
 
@@ -196,6 +937,14 @@ impl Parser {
         }
     }
 
+    /// Like [`check`](Self::check), but for the token after the next one.
+    fn check_next(&self, ty: &TokenType) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => !token.ty.matches(&TokenType::Eof) && token.ty.matches(ty),
+            None => false,
+        }
+    }
+
     fn check(&self, ty: &TokenType) -> bool {
         if self.is_at_end() {
             false
@@ -222,6 +971,270 @@ impl Parser {
     }
 
     fn previous(&self) -> Option<&Token> {
-        self.tokens.get(self.current - 1)
+        self.tokens.get(self.current.checked_sub(1)?)
+    }
+}
+
+/// Binding powers for [`PrattParser`], from loosest to tightest, as in
+/// chapter 17's `Precedence` enum.
+mod precedence {
+    pub const NONE: u8 = 0;
+    pub const ASSIGNMENT: u8 = 1;
+    pub const OR: u8 = 2;
+    pub const AND: u8 = 3;
+    pub const EQUALITY: u8 = 4;
+    pub const COMPARISON: u8 = 5;
+    pub const TERM: u8 = 6;
+    pub const FACTOR: u8 = 7;
+    pub const CAST: u8 = 8;
+    pub const UNARY: u8 = 9;
+    pub const CALL: u8 = 10;
+}
+
+type PrefixFn = fn(&mut PrattParser) -> Result<Expr>;
+type InfixFn = fn(&mut PrattParser, Expr) -> Result<Expr>;
+
+/// One row of the [`PrattParser`] table: how a token parses at the start of
+/// an expression, how it parses after one, and how tightly it binds there.
+struct ParseRule {
+    prefix: Option<PrefixFn>,
+    infix: Option<InfixFn>,
+    precedence: u8,
+}
+
+/// An operator-precedence parser for expressions, as in chapter 17,
+/// "Compiling Expressions".
+///
+/// It accepts the same expressions as [`Parser`], builds the same trees and
+/// reports the same errors, but drives parsing from a table of rules keyed
+/// by [`TokenType`] instead of one method per precedence level.
+pub struct PrattParser {
+    parser: Parser,
+}
+
+impl PrattParser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            parser: Parser::new(tokens),
+        }
+    }
+
+    /// Parses the tokens as a single expression, with nothing following it.
+    pub fn parse_expression(&mut self) -> Result<Expr> {
+        let expr = self.expression()?;
+        if !self.parser.is_at_end() {
+            return Err(self.parser.error("Expected end of expression."));
+        }
+        Ok(expr)
+    }
+
+    /// The table itself. Tokens without a row cannot appear in an
+    /// expression, and bind with [`precedence::NONE`] so that parsing stops
+    /// before them.
+    fn rule(ty: &TokenType) -> ParseRule {
+        use precedence::*;
+        use TokenType::*;
+        let (prefix, infix, precedence): (Option<PrefixFn>, Option<InfixFn>, u8) = match ty {
+            LeftParen => (Some(Self::grouping), Some(Self::call), CALL),
+            Dot => (None, Some(Self::get), CALL),
+            Minus => (Some(Self::unary), Some(Self::binary), TERM),
+            Plus => (None, Some(Self::binary), TERM),
+            Slash | Star => (None, Some(Self::binary), FACTOR),
+            Bang => (Some(Self::unary), None, NONE),
+            BangEqual | EqualEqual => (None, Some(Self::binary), EQUALITY),
+            Greater | GreaterEqual | Less | LessEqual => (None, Some(Self::binary), COMPARISON),
+            Equal => (None, Some(Self::assignment), ASSIGNMENT),
+            And => (None, Some(Self::logical), AND),
+            Or => (None, Some(Self::logical), OR),
+            Identifier(name) if name == "as" => (Some(Self::variable), Some(Self::cast), CAST),
+            Identifier(_) => (Some(Self::variable), None, NONE),
+            Fun => (Some(Self::lambda), None, NONE),
+            False | True | Nil | Number(_) | String(_) | RawString(_) => {
+                (Some(Self::literal), None, NONE)
+            }
+            _ => (None, None, NONE),
+        };
+        ParseRule {
+            prefix,
+            infix,
+            precedence,
+        }
+    }
+
+    /// The rule for the token the parser is looking at. `fun` only begins an
+    /// expression when `(` follows, as any other use is a declaration.
+    fn peek_rule(&self) -> ParseRule {
+        match self.parser.peek() {
+            Some(token)
+                if token.ty.matches(&TokenType::Fun)
+                    && !self.parser.check_next(&TokenType::LeftParen) =>
+            {
+                Self::rule(&TokenType::Eof)
+            }
+            Some(token) if !self.parser.is_at_end() => Self::rule(&token.ty),
+            _ => Self::rule(&TokenType::Eof),
+        }
+    }
+
+    /// The rule for the token just consumed.
+    fn previous_rule(&self) -> ParseRule {
+        Self::rule(&self.previous().ty)
+    }
+
+    fn previous(&self) -> Token {
+        self.parser
+            .previous()
+            .cloned()
+            .expect("Lost token after matching")
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        self.parse_precedence(precedence::ASSIGNMENT)
+    }
+
+    /// Parses an expression whose operators all bind at least as tightly as
+    /// `precedence`.
+    ///
+    /// An operator never binds more tightly than the one before it, since
+    /// the grammar has no rule for its left operand. Binary operators see to
+    /// this by parsing their right operand, but `as` ends at its type name,
+    /// so `x as number(1)` must not become a call.
+    fn parse_precedence(&mut self, precedence: u8) -> Result<Expr> {
+        let Some(prefix) = self.peek_rule().prefix else {
+            return Err(self.parser.error("Expected expression."));
+        };
+        self.parser.advance();
+        let mut expr = prefix(self)?;
+        let mut ceiling = precedence::CALL;
+        loop {
+            let next = self.peek_rule().precedence;
+            if next < precedence || next > ceiling {
+                break;
+            }
+            self.parser.advance();
+            let infix = self
+                .previous_rule()
+                .infix
+                .expect("Only infix operators have a precedence");
+            expr = infix(self, expr)?;
+            ceiling = next;
+        }
+        Ok(expr)
+    }
+
+    fn literal(&mut self) -> Result<Expr> {
+        Ok(Expr::Literal(self.previous()))
+    }
+
+    fn variable(&mut self) -> Result<Expr> {
+        Ok(Expr::Variable(self.previous()))
+    }
+
+    /// The body is a block of statements, which [`Parser`] parses.
+    fn lambda(&mut self) -> Result<Expr> {
+        self.parser.lambda()
+    }
+
+    /// `"(" sequence ")"`, where the sequence is a left-associative chain of
+    /// comma-separated expressions.
+    fn grouping(&mut self) -> Result<Expr> {
+        let mut expr = self.expression()?;
+        while self.parser.matches(&[TokenType::Comma]) {
+            let right = Box::new(self.expression()?);
+            expr = Expr::Sequence {
+                left: Box::new(expr),
+                right,
+            };
+        }
+        self.parser
+            .consume(&TokenType::RightParen, "Expected ')' after expression.")?;
+        Ok(Expr::Grouping(Box::new(expr)))
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let operator = self.previous();
+        let right = Box::new(self.parse_precedence(precedence::UNARY)?);
+        Ok(Expr::Unary { operator, right })
+    }
+
+    /// Binary operators are left-associative, so the right operand may only
+    /// contain operators that bind more tightly.
+    fn binary(&mut self, left: Expr) -> Result<Expr> {
+        let operator = self.previous();
+        let precedence = self.previous_rule().precedence;
+        let right = Box::new(self.parse_precedence(precedence + 1)?);
+        Ok(Expr::Binary {
+            left: Box::new(left),
+            operator,
+            right,
+        })
+    }
+
+    fn logical(&mut self, left: Expr) -> Result<Expr> {
+        let operator = self.previous();
+        let precedence = self.previous_rule().precedence;
+        let right = Box::new(self.parse_precedence(precedence + 1)?);
+        Ok(Expr::Logical {
+            left: Box::new(left),
+            operator,
+            right,
+        })
+    }
+
+    /// Assignment is right-associative, and like [`Parser`] the value is
+    /// parsed before the target is checked.
+    fn assignment(&mut self, target: Expr) -> Result<Expr> {
+        let equals = self.previous();
+        let value = Box::new(self.parse_precedence(precedence::ASSIGNMENT)?);
+        match target {
+            Expr::Variable(name) => Ok(Expr::Assign { name, value }),
+            _ => Err(Error::Syntax {
+                file: equals.file(),
+                line: equals.line,
+                message: "Invalid assignment target.",
+            }),
+        }
+    }
+
+    fn cast(&mut self, expr: Expr) -> Result<Expr> {
+        let target_type = self.parser.type_name()?;
+        Ok(Expr::Cast {
+            expr: Box::new(expr),
+            target_type,
+        })
+    }
+
+    fn get(&mut self, object: Expr) -> Result<Expr> {
+        let name = self.parser.consume(
+            &TokenType::Identifier(String::new()),
+            "Expected property name after '.'.",
+        )?;
+        Ok(Expr::Get {
+            object: Box::new(object),
+            name,
+        })
+    }
+
+    fn call(&mut self, callee: Expr) -> Result<Expr> {
+        let mut arguments = vec![];
+        if !self.parser.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARITY {
+                    return Err(self.parser.error("Can't have more than 255 arguments."));
+                }
+                arguments.push(self.expression()?);
+                if !self.parser.matches(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self
+            .parser
+            .consume(&TokenType::RightParen, "Expected ')' after arguments.")?;
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            paren,
+            arguments,
+        })
     }
 }
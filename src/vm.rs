@@ -0,0 +1,118 @@
+use crate::chunk::{Chunk, OpCode};
+use crate::error::Result;
+use crate::interpret::Value;
+
+/// A stack-based virtual machine that executes a compiled [`Chunk`].
+pub struct Vm {
+    chunk: Chunk,
+    ip: usize,
+    stack: Vec<Value>,
+}
+
+impl Vm {
+    pub fn new(chunk: Chunk) -> Self {
+        Self {
+            chunk,
+            ip: 0,
+            stack: Vec::with_capacity(256),
+        }
+    }
+
+    /// Runs the chunk until it returns, producing the returned value.
+    ///
+    /// The chunk is validated first, so bytecode that would read past the
+    /// end of the code or pop an empty stack fails with
+    /// [`Error::InvalidBytecode`](crate::error::Error::InvalidBytecode)
+    /// instead of running.
+    pub fn run(&mut self) -> Result<Value> {
+        self.chunk.validate()?;
+        loop {
+            let byte = self.read_byte();
+            let op = OpCode::try_from(byte).expect("validated chunks hold only opcodes");
+            match op {
+                OpCode::Constant => {
+                    let index = self.read_byte() as usize;
+                    let constant = self.chunk.constants[index].clone();
+                    self.stack.push(constant);
+                }
+                OpCode::Nil => self.stack.push(Value::Nil),
+                OpCode::True => self.stack.push(true.into()),
+                OpCode::False => self.stack.push(false.into()),
+                OpCode::Pop => {
+                    self.pop();
+                }
+                OpCode::Equal => {
+                    let (left, right) = self.pop_pair();
+                    self.stack.push((left == right).into());
+                }
+                OpCode::Greater => {
+                    let (left, right) = self.pop_numbers()?;
+                    self.stack.push((left > right).into());
+                }
+                OpCode::Less => {
+                    let (left, right) = self.pop_numbers()?;
+                    self.stack.push((left < right).into());
+                }
+                OpCode::Add => {
+                    let value = match self.pop_pair() {
+                        (Value::Number(l), Value::Number(r)) => (l + r).into(),
+                        (Value::String(l), Value::String(r)) => (l + &r).into(),
+                        (l, r) => return Err(Value::add_type_error(self.line(), &l, &r)),
+                    };
+                    self.stack.push(value);
+                }
+                OpCode::Subtract => {
+                    let (left, right) = self.pop_numbers()?;
+                    self.stack.push((left - right).into());
+                }
+                OpCode::Multiply => {
+                    let (left, right) = self.pop_numbers()?;
+                    self.stack.push((left * right).into());
+                }
+                OpCode::Divide => {
+                    let (left, right) = self.pop_numbers()?;
+                    self.stack.push((left / right).into());
+                }
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.stack.push(!value);
+                }
+                OpCode::Negate => {
+                    let value = self.pop().into_double(self.line())?;
+                    self.stack.push((-value).into());
+                }
+                OpCode::Print => println!("{}", self.pop()),
+                OpCode::Return => return Ok(self.pop()),
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let byte = self.chunk.code[self.ip];
+        self.ip += 1;
+        byte
+    }
+
+    /// The source line of the instruction that was just read.
+    fn line(&self) -> usize {
+        self.chunk.lines[self.ip - 1]
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("validated chunks never pop an empty stack")
+    }
+
+    fn pop_pair(&mut self) -> (Value, Value) {
+        let right = self.pop();
+        let left = self.pop();
+        (left, right)
+    }
+
+    fn pop_numbers(&mut self) -> Result<(f64, f64)> {
+        let line = self.line();
+        let (left, right) = self.pop_pair();
+        Ok((left.into_double(line)?, right.into_double(line)?))
+    }
+}
@@ -0,0 +1,46 @@
+use insta::assert_snapshot;
+use lox::syntax::Expr;
+
+fn print(source: &str) -> String {
+    source.parse::<Expr>().unwrap().to_string()
+}
+
+#[test]
+fn grouped_unary() {
+    assert_snapshot!("grouped_unary", print("(-123) * (45.67)"));
+}
+
+#[test]
+fn precedence() {
+    assert_snapshot!("precedence", print("1 + 2 * 3 - 4 / 5"));
+}
+
+#[test]
+fn comparison_and_equality() {
+    assert_snapshot!("comparison_and_equality", print("1 < 2 == !(3 >= 4)"));
+}
+
+#[test]
+fn literals() {
+    assert_snapshot!("literals", print(r#""lox" != nil == true != false"#));
+}
+
+#[test]
+fn nested_unary() {
+    assert_snapshot!("nested_unary", print("!!-(-1)"));
+}
+
+#[test]
+fn sequence() {
+    assert_snapshot!("sequence", print("(1, 2 + 3, nil)"));
+}
+
+#[test]
+fn call() {
+    assert_snapshot!("call", print("f(1, g())(x)"));
+}
+
+#[test]
+fn logical() {
+    assert_snapshot!("logical", print("a or b and !c"));
+}
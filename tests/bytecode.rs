@@ -0,0 +1,98 @@
+use lox::chunk::{Chunk, OpCode, FORMAT_VERSION};
+use lox::error::Error;
+use lox::interpret::Value;
+use lox::vm::Vm;
+
+#[test]
+fn round_trips_through_bytes() {
+    let bytes = lox::compile("(1 + 2) * 3 == 9").unwrap();
+    let chunk = Chunk::from_bytes(&bytes).unwrap();
+    assert_eq!(Vm::new(chunk).run().unwrap(), Value::Boolean(true));
+
+    let bytes = lox::compile(r#""lox" + "script" != nil"#).unwrap();
+    assert_eq!(
+        Chunk::from_bytes(&bytes).unwrap().to_bytes().unwrap(),
+        bytes
+    );
+    lox::execute(&bytes).unwrap();
+}
+
+#[test]
+fn rejects_other_versions() {
+    let mut bytes = lox::compile("1 + 2").unwrap();
+    bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        lox::execute(&bytes),
+        Err(Error::BytecodeVersion { .. })
+    ));
+}
+
+#[test]
+fn refuses_to_serialize_runtime_values() {
+    let mut chunk = Chunk::new();
+    chunk.add_constant(Value::Bytes(Default::default()));
+    assert!(matches!(chunk.to_bytes(), Err(Error::InvalidBytecode(_))));
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn refuses_to_serialize_regexps() {
+    let mut chunk = Chunk::new();
+    chunk.add_constant(lox::Lox::new().eval(r#"regexp("a")"#).unwrap());
+    assert!(matches!(chunk.to_bytes(), Err(Error::InvalidBytecode(_))));
+}
+
+#[test]
+fn rejects_malformed_bytecode() {
+    let bytes = lox::compile("1 + 2").unwrap();
+    for len in 0..bytes.len() {
+        assert!(matches!(
+            lox::execute(&bytes[..len]),
+            Err(Error::InvalidBytecode(_))
+        ));
+    }
+    assert!(lox::execute(b"not bytecode").is_err());
+}
+
+#[test]
+fn rejects_code_the_vm_cannot_run() {
+    let mut underflow = Chunk::new();
+    underflow.write_op(OpCode::Add, 1);
+    underflow.write_op(OpCode::Return, 1);
+
+    // The operand of the last constant is the Return opcode's byte.
+    let mut operand_at_end = Chunk::new();
+    for n in 0..20 {
+        operand_at_end.add_constant(Value::Number(n.into()));
+    }
+    operand_at_end.write_op(OpCode::Constant, 1);
+    operand_at_end.write(OpCode::Return as u8, 1);
+
+    let mut no_return = Chunk::new();
+    no_return.write_op(OpCode::Nil, 1);
+
+    for chunk in [underflow, operand_at_end, no_return] {
+        assert!(matches!(
+            Chunk::from_bytes(&chunk.to_bytes().unwrap()),
+            Err(Error::InvalidBytecode(_))
+        ));
+        assert!(matches!(
+            Vm::new(chunk).run(),
+            Err(Error::InvalidBytecode(_))
+        ));
+    }
+}
+
+#[test]
+fn disassembles_malformed_chunks_without_panicking() {
+    let mut missing_constant = Chunk::new();
+    missing_constant.write_op(OpCode::Constant, 1);
+    missing_constant.write(7, 1);
+
+    let mut operand_at_end = Chunk::new();
+    operand_at_end.write_op(OpCode::Constant, 1);
+
+    for (chunk, next) in [(missing_constant, 2), (operand_at_end, 1)] {
+        assert_eq!(lox::debug::disassemble_instruction(&chunk, 0), next);
+    }
+}
@@ -0,0 +1,40 @@
+use lox::environment::Environment;
+use lox::interpret::Value;
+use lox::scanner::Token;
+
+fn name(name: &str) -> Token {
+    lox::scan(name).unwrap().remove(0)
+}
+
+#[test]
+fn frozen_globals_are_shared_and_never_modified() {
+    let mut prelude = Environment::new();
+    prelude.define("version", Value::Number(1.0));
+    prelude.define("greeting", Value::from("hello".to_string()));
+    let frozen = prelude.freeze();
+
+    let mut first = Environment::new_with_frozen_globals(&frozen);
+    let second = Environment::new_with_frozen_globals(&frozen);
+    assert_eq!(first.get(&name("version")).unwrap(), Value::Number(1.0));
+
+    first.assign(&name("version"), Value::Number(2.0)).unwrap();
+    assert_eq!(first.get(&name("version")).unwrap(), Value::Number(2.0));
+    assert_eq!(second.get(&name("version")).unwrap(), Value::Number(1.0));
+    assert_eq!(frozen.values()["version"], Value::Number(1.0));
+
+    assert!(first.assign(&name("missing"), Value::Nil).is_err());
+    let names: Vec<_> = first.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["version", "greeting"]);
+}
+
+#[test]
+fn capacity_is_reserved_up_front() {
+    let environment = Environment::with_capacity(8);
+    assert!(environment.values().capacity() >= 8);
+
+    let enclosing = std::rc::Rc::new(std::cell::RefCell::new(environment));
+    let mut inner = Environment::new_enclosed_with_capacity(enclosing, 3);
+    assert!(inner.values().capacity() >= 3);
+    inner.define("x", Value::Nil);
+    assert!(inner.get(&name("x")).is_ok());
+}
@@ -0,0 +1,97 @@
+use lox::error::Error;
+use lox::syntax::Expr;
+
+#[test]
+fn io_errors_chain_their_source() {
+    let err = lox::run_file("tests/does-not-exist.lox", Default::default()).unwrap_err();
+    let err = anyhow::Error::from(err);
+
+    assert!(err.downcast_ref::<Error>().is_some());
+    let chain: Vec<_> = err.chain().collect();
+    assert_eq!(chain.len(), 2);
+    assert!(chain[1].is::<std::io::Error>());
+}
+
+#[test]
+fn syntax_errors_have_no_source() {
+    let err = "1 +".parse::<Expr>().unwrap_err();
+    let err = anyhow::Error::from(err);
+
+    assert_eq!(err.chain().count(), 1);
+    assert_eq!(err.to_string(), "[line 1] Error: Expected expression.");
+}
+
+#[test]
+fn converts_into_boxed_error() {
+    let err = "@".parse::<Expr>().unwrap_err();
+    let boxed: Box<dyn std::error::Error + Send + Sync> = err.into();
+
+    assert!(boxed.downcast_ref::<Error>().is_some());
+}
+
+#[test]
+fn context_points_at_the_source_line() {
+    let source = "var a = 1;\n\tprint (a;\n";
+    let err = lox::parse(source).unwrap_err();
+    assert_eq!(
+        err.with_context(source).to_string(),
+        "[line 2] Error at ';': Expected ')' after expression.\n\
+         2 | \tprint (a;\n  \
+           | \t        ^"
+    );
+
+    // Without a column there is no caret, and without a line no context.
+    let err = lox::parse("1 = 2;").unwrap_err();
+    assert_eq!(
+        err.with_context("1 = 2;").to_string(),
+        "[line 1] Error: Invalid assignment target.\n1 | 1 = 2;"
+    );
+    assert_eq!(
+        Error::Timeout.with_context(source).to_string(),
+        "Error: Execution timed out."
+    );
+}
+
+#[test]
+fn converts_into_io_error() {
+    fn parse(source: &str) -> std::io::Result<()> {
+        lox::parse(source)?;
+        Ok(())
+    }
+    let err = parse("1 +").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Other);
+    assert!(err.get_ref().unwrap().is::<Error>());
+
+    let err = std::io::Error::from(
+        lox::run_file("tests/does-not-exist.lox", Default::default()).unwrap_err(),
+    );
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn errors_know_their_phase() {
+    use lox::error::Phase;
+
+    let phase = |source: &str| lox::Lox::new().run_string(source).unwrap_err().phase();
+    assert_eq!(phase("print (1;"), Phase::Parse);
+    assert_eq!(phase("print -nil;"), Phase::Runtime);
+    assert_eq!(phase("print missing;"), Phase::Runtime);
+
+    let err = lox::run_file("tests/does-not-exist.lox", Default::default()).unwrap_err();
+    assert_eq!(err.phase(), Phase::IO);
+    assert_eq!(phase("print @;"), Phase::Scan);
+    assert_eq!(phase("print \"open"), Phase::Parse);
+}
+
+#[test]
+fn foreign_errors_are_chained() {
+    let parse_error = "x".parse::<i32>().unwrap_err();
+    let err = Error::chain(3, parse_error.clone());
+    assert_eq!(err.to_string(), format!("[line 3] Error: {parse_error}"));
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.is::<std::num::ParseIntError>());
+    assert_eq!(err.phase(), lox::error::Phase::Runtime);
+
+    let err = Error::chain(1, "disk full");
+    assert_eq!(err.to_string(), "[line 1] Error: disk full");
+}
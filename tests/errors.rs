@@ -0,0 +1,70 @@
+//! Golden-file tests for error output. Each `tests/errors/<name>.lox` is run
+//! through the interpreter and its stderr is compared byte-for-byte with
+//! `tests/errors/<name>.expected`. When a message changes on purpose, update
+//! the matching `.expected` file along with it.
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn error_messages_match_golden_files() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/errors");
+    let mut scripts: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts found in {}", dir.display());
+
+    for script in scripts {
+        let expected = std::fs::read_to_string(script.with_extension("expected"))
+            .unwrap_or_else(|e| panic!("missing golden file for {}: {e}", script.display()));
+        let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+            .arg(script.file_name().unwrap())
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            expected,
+            "stderr of {}",
+            script.display()
+        );
+    }
+}
+
+#[test]
+fn allow_shadowing_silences_the_linter() {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/errors/shadowed_variable.lox");
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--allow-shadowing")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn trace_writes_each_statement_before_it_runs() {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/errors/call_stack.lox");
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--trace")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(70));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("[line 1] fun\n"), "{stderr}");
+}
+
+#[test]
+fn flags_without_a_script_print_usage() {
+    let output = Command::new(env!("CARGO_BIN_EXE_lox"))
+        .arg("--trace")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(64));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("Usage: "), "{stderr}");
+}
@@ -0,0 +1,681 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use lox::interpret::{Interpreter, Value, STDLIB};
+use lox::scanner::Scanner;
+use lox::syntax::Parser;
+
+fn run(interpreter: &mut Interpreter, source: &str) -> lox::error::Result<()> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens()?;
+    let statements = Parser::new(tokens).parse()?;
+    interpreter.execute_all(&statements, &mut vec![])
+}
+
+#[test]
+fn globals_round_trip_through_a_map() {
+    let mut interpreter = Interpreter::new();
+    interpreter.import_globals(HashMap::from([
+        ("width".to_string(), Value::Number(3.0)),
+        ("height".to_string(), Value::Number(4.0)),
+    ]));
+    run(
+        &mut interpreter,
+        "var area = width * height; { var hidden = 1; }",
+    )
+    .unwrap();
+
+    let globals = interpreter.export_globals();
+    assert_eq!(globals.len(), 3);
+    assert_eq!(globals["area"], Value::Number(12.0));
+    assert!(!globals.contains_key("hidden"));
+}
+
+#[test]
+fn functions_return_values_and_close_over_their_scope() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        r#"
+        fun makeCounter() {
+          var count = 0;
+          fun increment() {
+            count = count + 1;
+            return count;
+          }
+          return increment;
+        }
+        var counter = makeCounter();
+        counter();
+        var second = counter();
+        fun noReturn() { 1; }
+        var nothing = noReturn();
+        var now = clock();
+        "#,
+    )
+    .unwrap();
+
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["second"], Value::Number(2.0));
+    assert_eq!(globals["nothing"], Value::Nil);
+    assert!(matches!(globals["now"], Value::Number(_)));
+    assert_eq!(globals["counter"].to_string(), "<fn increment>");
+    assert!(!globals.contains_key("clock"));
+}
+
+#[test]
+fn anonymous_functions_are_closures() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        r#"
+        fun apply(f, x) { return f(x); }
+        var offset = 10;
+        var shifted = apply(fun (x) { return x + offset; }, 5);
+        var immediate;
+        fun (n) { immediate = n; }(7);
+        var anonymous = fun () {};
+        "#,
+    )
+    .unwrap();
+
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["shifted"], Value::Number(15.0));
+    assert_eq!(globals["immediate"], Value::Number(7.0));
+    assert_eq!(globals["anonymous"].to_string(), "<fn>");
+}
+
+#[test]
+fn calls_check_the_callee_and_its_arity() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "fun pair(a, b) { return a + b; }").unwrap();
+
+    let err = run(&mut interpreter, "pair(1);").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Error: Expected 2 arguments but got 1."
+    );
+    let err = run(&mut interpreter, r#""pair"(1, 2);"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Type error: Can only call functions and classes, not string."
+    );
+}
+
+#[test]
+fn strings_respect_the_length_limit() {
+    let mut interpreter = Interpreter::new().with_max_string_length(4);
+    run(&mut interpreter, r#"var s = "ab" + "cd";"#).unwrap();
+
+    let err = run(&mut interpreter, r#"s = s + "e";"#).unwrap_err();
+    assert!(matches!(
+        err,
+        lox::error::Error::ResourceLimit {
+            line: 1,
+            limit: "string",
+            max: 4,
+            ..
+        }
+    ));
+    assert_eq!(
+        interpreter.export_globals()["s"],
+        Value::from("abcd".to_string())
+    );
+
+    interpreter.reset();
+    assert!(run(&mut interpreter, r#"var t = "abc" + "de";"#).is_err());
+
+    for source in [
+        r#""ab".replace("b", "cdef");"#,
+        r#""ŉŉ".upper();"#,
+        "12345 as string;",
+    ] {
+        assert!(
+            matches!(
+                run(&mut interpreter, source),
+                Err(lox::error::Error::ResourceLimit { line: 1, .. })
+            ),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn evaluate_at_the_crate_root() {
+    assert_eq!(lox::evaluate("1 + 2 * 3").unwrap(), Value::Number(7.0));
+    assert!(lox::evaluate("nil + 1").is_err());
+    assert!(lox::evaluate("print 1;").is_err());
+}
+
+#[test]
+fn the_deadline_ends_with_the_run() {
+    let body = "var x = 1;\n".repeat(2000);
+    let mut interpreter = Interpreter::new().with_timeout(std::time::Duration::from_millis(20));
+    run(&mut interpreter, &format!("fun f() {{\n{body}}}")).unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(40));
+
+    let call: lox::syntax::Expr = "f()".parse().unwrap();
+    assert_eq!(
+        interpreter.evaluate(&call, &mut vec![]).unwrap(),
+        Value::Nil
+    );
+}
+
+#[test]
+fn long_runs_stop_at_the_timeout() {
+    let program = "var x = 1;\n".repeat(5000);
+    let mut interpreter = Interpreter::new().with_timeout(std::time::Duration::ZERO);
+    assert!(matches!(
+        run(&mut interpreter, &program),
+        Err(lox::error::Error::Timeout)
+    ));
+
+    let mut interpreter = Interpreter::new().with_timeout(std::time::Duration::from_secs(60));
+    run(&mut interpreter, &program).unwrap();
+}
+
+#[test]
+fn another_thread_can_interrupt_a_run() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let runner = std::thread::spawn(move || {
+        let mut interpreter = Interpreter::new();
+        sender.send(interpreter.interrupt_signal()).unwrap();
+        let result = run(&mut interpreter, "while (true) {}");
+        (result, run(&mut interpreter, "var x = 1;"))
+    });
+    let signal = receiver.recv().unwrap();
+    signal.store(true, std::sync::atomic::Ordering::Relaxed);
+
+    let (interrupted, next) = runner.join().unwrap();
+    assert!(matches!(interrupted, Err(lox::error::Error::Interrupted)));
+    next.unwrap();
+}
+
+#[test]
+fn counts_iterations_and_calls() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        "fun f(n) { return n; }\nfor (var i = 0; i < 3; i = i + 1) f(clock());",
+    )
+    .unwrap();
+    assert_eq!(interpreter.iteration_count(), 4);
+    assert_eq!(interpreter.function_call_count(), 6);
+
+    interpreter.reset_stats();
+    assert_eq!(interpreter.iteration_count(), 0);
+    assert_eq!(interpreter.function_call_count(), 0);
+}
+
+#[test]
+fn strict_mode_requires_boolean_conditions() {
+    let program = "var n = 0; while (n < 3) n = n + 1; if (!false) n = -n;";
+    let mut interpreter = Interpreter::new();
+    interpreter.enable_strict_mode();
+    run(&mut interpreter, program).unwrap();
+    assert_eq!(interpreter.export_globals()["n"], Value::Number(-3.0));
+
+    for (source, message) in [
+        (
+            "if (1) {}",
+            "[line 1] Type error: Expected boolean but got number.",
+        ),
+        (
+            "\nwhile (nil) {}",
+            "[line 2] Type error: Expected boolean but got nil.",
+        ),
+        (
+            "!\"s\";",
+            "[line 1] Type error: Expected boolean but got string.",
+        ),
+    ] {
+        let err = run(&mut interpreter, source).unwrap_err();
+        assert_eq!(err.to_string(), message);
+    }
+    run(&mut Interpreter::new(), "if (1) {} while (nil) {} !\"s\";").unwrap();
+}
+
+#[test]
+fn print_output_can_be_captured() {
+    let mut interpreter = Interpreter::new();
+    let capture = interpreter.capture_print();
+    run(&mut interpreter, "print \"a\"; print 1 + 1;").unwrap();
+    assert_eq!(capture.output(), "\"a\"\n2\n");
+    drop(capture);
+
+    let capture = interpreter.capture_print();
+    assert_eq!(capture.output(), "");
+}
+
+#[test]
+fn dropping_an_earlier_capture_keeps_the_later_one() {
+    let mut interpreter = Interpreter::new();
+    let first = interpreter.capture_print();
+    let second = interpreter.capture_print();
+    drop(first);
+    run(&mut interpreter, "print 1;").unwrap();
+    assert_eq!(second.output(), "1\n");
+}
+
+#[test]
+fn control_flow() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        r#"
+        fun fib(n) {
+          if (n < 2) return n;
+          return fib(n - 1) + fib(n - 2);
+        }
+        var fib10 = fib(10);
+
+        var sum = 0;
+        for (var i = 1; i <= 4; i = i + 1) sum = sum + i;
+
+        var countdown = 3;
+        while (countdown > 0) countdown = countdown - 1;
+
+        var either = nil or "default";
+        var both = 1 and false;
+        var skipped = true;
+        false and (skipped = false);
+        "#,
+    )
+    .unwrap();
+
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["fib10"], Value::Number(55.0));
+    assert_eq!(globals["sum"], Value::Number(10.0));
+    assert_eq!(globals["countdown"], Value::Number(0.0));
+    assert_eq!(globals["either"], Value::from("default".to_string()));
+    assert_eq!(globals["both"], Value::Boolean(false));
+    assert_eq!(globals["skipped"], Value::Boolean(true));
+    assert!(!globals.contains_key("i"));
+}
+
+#[test]
+fn lox_functions_can_be_called_from_rust() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        "fun validate(input) { return input != nil and input > 0; }",
+    )
+    .unwrap();
+    let validate = interpreter.export_globals().remove("validate").unwrap();
+
+    assert_eq!(
+        interpreter
+            .call(validate.clone(), vec![Value::Number(3.0)])
+            .unwrap(),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        interpreter
+            .call(validate.clone(), vec![Value::Nil])
+            .unwrap(),
+        Value::Boolean(false)
+    );
+    assert!(matches!(
+        interpreter.call(validate, vec![]),
+        Err(lox::error::Error::Arity {
+            expected: 1,
+            got: 0,
+            ..
+        })
+    ));
+    assert!(interpreter.call(Value::Nil, vec![]).is_err());
+
+    let mut interpreter = Interpreter::new().with_timeout(std::time::Duration::from_millis(20));
+    run(&mut interpreter, "fun spin() { while (true) {} }").unwrap();
+    let spin = interpreter.export_globals().remove("spin").unwrap();
+    assert!(matches!(
+        interpreter.call(spin, vec![]),
+        Err(lox::error::Error::Timeout)
+    ));
+    assert_eq!(interpreter.call_stack()[0].line, 0);
+}
+
+#[test]
+fn unbounded_recursion_is_a_stack_overflow() {
+    let mut interpreter = Interpreter::new();
+    let source = "fun f(n) {\n  return 1 + f(n + 1);\n}\nf(0);";
+    assert!(matches!(
+        run(&mut interpreter, source),
+        Err(lox::error::Error::StackOverflow { line: 2, .. })
+    ));
+    assert!(matches!(
+        interpreter.run_sandboxed(source, &[]),
+        Err(lox::error::Error::StackOverflow { line: 2, .. })
+    ));
+
+    // Deep calls move off a small thread stack instead of overflowing it.
+    std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(|| {
+            let mut interpreter = Interpreter::new().with_max_call_depth(5000);
+            let source = "fun f(n) { if (n == 0) return 0; return 1 + f(n - 1); } f(4000);";
+            run(&mut interpreter, source).unwrap();
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    let mut interpreter = Interpreter::new().with_max_call_depth(3);
+    run(&mut interpreter, "fun f(n) { if (n > 0) f(n - 1); }").unwrap();
+    run(&mut interpreter, "f(2);").unwrap();
+    assert!(run(&mut interpreter, "f(3);").is_err());
+}
+
+#[test]
+fn self_tail_calls_do_not_nest() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        "fun count(n) { if (n == 0) return; count(n - 1); }
+         fun find(n) { if (n == 0) return \"found\"; return find(n - 1); }
+         fun drop(n) { if (n == 0) return \"dropped\"; drop(n - 1); }
+         count(10000);
+         var found = find(10000);
+         var dropped = drop(3);",
+    )
+    .unwrap();
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["found"], Value::from("found".to_string()));
+    assert_eq!(globals["dropped"], Value::Nil);
+
+    // A closure made on each pass sees that pass's parameter.
+    let capture = interpreter.capture_print();
+    run(
+        &mut interpreter,
+        "fun last(n, previous) {
+           fun get() { return n; }
+           if (n == 0) return previous;
+           return last(n - 1, get);
+         }
+         print last(3, nil)();",
+    )
+    .unwrap();
+    assert_eq!(capture.output(), "1\n");
+}
+
+#[test]
+fn runs_without_a_warning_list_keep_theirs_until_taken() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .load_prelude("fun half(n) { return n / 0; }")
+        .unwrap();
+    interpreter.run_sandboxed("return 1 / 0;", &[]).unwrap();
+    let half = interpreter.export_globals()["half"].clone();
+    interpreter.call(half, vec![Value::Number(4.0)]).unwrap();
+
+    let warnings = interpreter.take_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings
+        .iter()
+        .all(|warning| warning.message == "Division by zero."));
+    assert!(interpreter.take_warnings().is_empty());
+}
+
+#[test]
+fn sandboxed_scripts_see_only_allowed_globals() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "var secret = 42; var limit = 10;").unwrap();
+
+    let result = interpreter
+        .run_sandboxed(
+            "var doubled = limit * 2; limit = 0; return doubled;",
+            &["limit"],
+        )
+        .unwrap();
+    assert_eq!(result, Value::Number(20.0));
+
+    for source in ["return secret;", "return clock();"] {
+        let err = interpreter.run_sandboxed(source, &["limit"]).unwrap_err();
+        assert!(
+            matches!(err, lox::error::Error::UndefinedVariable { .. }),
+            "{source}"
+        );
+    }
+    assert!(interpreter
+        .run_sandboxed("return clock();", &["clock"])
+        .is_ok());
+
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["limit"], Value::Number(10.0));
+    assert!(!globals.contains_key("doubled"));
+}
+
+#[test]
+fn sandboxed_scripts_cannot_import() {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/modules/main.lox");
+    let mut interpreter = Interpreter::new().with_script_path(script);
+    let err = interpreter
+        .run_sandboxed(r#"import "math.lox";"#, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Error: Sandboxed code cannot import module 'math.lox'."
+    );
+    run(&mut interpreter, r#"import "math.lox";"#).unwrap();
+}
+
+#[test]
+fn imports_define_the_module_exports() {
+    let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/modules/main.lox");
+    let mut interpreter = Interpreter::new().with_script_path(script);
+    run(
+        &mut interpreter,
+        "var two = \"shadowed\"; { import \"math.lox\"; var nine = square(3); }",
+    )
+    .unwrap();
+    // The block's scope received the imports, not the globals.
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["two"], Value::from("shadowed".to_string()));
+    assert!(!globals.contains_key("square"));
+
+    run(&mut interpreter, "import \"math.lox\";").unwrap();
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["four"], Value::Number(4.0));
+    assert!(globals.contains_key("square"));
+    // math.lox imports constants.lox without exporting what it got.
+    assert_eq!(globals["two"], Value::from("shadowed".to_string()));
+    assert!(!globals.contains_key("constants"));
+
+    run(&mut interpreter, "import \"constants.lox\";").unwrap();
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["two"], Value::Number(2.0));
+    assert_eq!(globals["constants"], Value::Number(3.0));
+    assert!(!globals.contains_key("hidden"));
+
+    assert!(matches!(
+        run(&mut interpreter, "import \"cycle_a.lox\";"),
+        Err(lox::error::Error::Import { line: 1, .. })
+    ));
+}
+
+#[test]
+fn the_prelude_adds_to_the_globals() {
+    let mut interpreter = Interpreter::new();
+    run(&mut interpreter, "var kept = 1;").unwrap();
+    interpreter.load_prelude(STDLIB).unwrap();
+    run(
+        &mut interpreter,
+        "var a = abs(-3); var b = clamp(12, 0, 10); var c = pow(2, 10);",
+    )
+    .unwrap();
+
+    let globals = interpreter.export_globals();
+    assert_eq!(globals["kept"], Value::Number(1.0));
+    assert_eq!(globals["a"], Value::Number(3.0));
+    assert_eq!(globals["b"], Value::Number(10.0));
+    assert_eq!(globals["c"], Value::Number(1024.0));
+}
+
+#[test]
+fn scopes_kept_alive_by_closures_are_collected() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        "fun outer() { fun inner() {} }
+         fun make() { var x = 1; fun get() { return x; } return get; }
+         var kept = make();",
+    )
+    .unwrap();
+    let before = interpreter.heap_usage();
+
+    run(
+        &mut interpreter,
+        "for (var i = 0; i < 100; i = i + 1) outer();",
+    )
+    .unwrap();
+    assert_eq!(interpreter.heap_usage(), before + 100);
+    interpreter.gc();
+    assert_eq!(interpreter.heap_usage(), before);
+
+    // A closure the embedder holds keeps its scope alive.
+    let kept = interpreter.export_globals().remove("kept").unwrap();
+    run(&mut interpreter, "kept = nil;").unwrap();
+    interpreter.gc();
+    assert_eq!(interpreter.call(kept, vec![]).unwrap(), Value::Number(1.0));
+
+    // Collection also runs on its own.
+    run(
+        &mut interpreter,
+        "for (var i = 0; i < 3000; i = i + 1) outer();",
+    )
+    .unwrap();
+    assert!(interpreter.heap_usage() < 1000);
+}
+
+#[test]
+fn with_binds_variables_for_its_body() {
+    let mut interpreter = Interpreter::new();
+    let capture = interpreter.capture_print();
+    run(
+        &mut interpreter,
+        r#"
+        var x = 10;
+        with (x = x + 1, y = x) {
+          print x;
+          print y;
+        }
+        print x;
+        fun twice(n) {
+          with (m = n * 2) return m;
+        }
+        print twice(4);
+        "#,
+    )
+    .unwrap();
+    assert_eq!(capture.output(), "11\n10\n10\n8\n");
+    assert!(run(&mut interpreter, "with (z = 1) print z; print z;").is_err());
+}
+
+#[test]
+fn with_is_not_reserved() {
+    let mut interpreter = Interpreter::new();
+    let capture = interpreter.capture_print();
+    run(
+        &mut interpreter,
+        r#"
+        fun with(value) { return value + 1; }
+        var answer = with(1);
+        with(answer);
+        with (with = answer) print with;
+        var setting;
+        fun with(value) { setting = value; return value; }
+        with(setting = 3);
+        print setting;
+        with(setting = 4) + 1;
+        print setting;
+        "#,
+    )
+    .unwrap();
+    assert_eq!(capture.output(), "2\n3\n4\n");
+}
+
+#[test]
+fn mem_usage_counts_strings_in_live_scopes() {
+    let mut interpreter = Interpreter::new();
+    let empty = interpreter.mem_usage();
+    assert_eq!(empty.string_bytes, 0);
+    assert_eq!(empty.binary_bytes, 0);
+
+    run(
+        &mut interpreter,
+        "var greeting = \"hello\";
+         fun make() { var name = \"world\"; fun get() { return name; } return get; }
+         var get = make();
+         var data = string_to_bytes(\"abc\");
+         var alias = data;",
+    )
+    .unwrap();
+    let stats = interpreter.mem_usage();
+    assert_eq!(stats.string_bytes, 10);
+    assert_eq!(stats.binary_bytes, 3);
+    assert_eq!((stats.array_elements, stats.map_entries), (0, 0));
+    assert_eq!(stats.heap_objects, interpreter.heap_usage());
+
+    run(&mut interpreter, "greeting = \"hi\"; var copy = data;").unwrap();
+    assert_eq!(interpreter.mem_usage().string_bytes, 7);
+    assert_eq!(interpreter.mem_usage().binary_bytes, 3);
+
+    run(
+        &mut interpreter,
+        "greeting = nil; get = nil; data = nil; alias = nil; copy = nil;",
+    )
+    .unwrap();
+    interpreter.gc();
+    assert_eq!(interpreter.mem_usage(), empty);
+}
+
+#[test]
+fn call_stack_shows_where_an_error_happened() {
+    let mut interpreter = Interpreter::new();
+    run(
+        &mut interpreter,
+        "fun check(n) { return -n; }
+         fun outer(n) {
+           return check(n);
+         }
+         outer(1);",
+    )
+    .unwrap();
+    assert!(interpreter.call_stack().is_empty());
+
+    run(&mut interpreter, "outer(\"x\");").unwrap_err();
+    let frames: Vec<_> = interpreter
+        .call_stack()
+        .iter()
+        .map(|frame| (frame.function_name.as_str(), frame.line))
+        .collect();
+    assert_eq!(frames, [("outer", 1), ("check", 3)]);
+    assert!(interpreter
+        .call_stack()
+        .iter()
+        .all(|frame| frame.file.is_none()));
+
+    run(&mut interpreter, "outer(2);").unwrap();
+    assert!(interpreter.call_stack().is_empty());
+
+    let tokens = Scanner::new("outer(nil);".to_string())
+        .with_source_name("script.lox")
+        .scan_tokens()
+        .unwrap();
+    let statements = Parser::new(tokens).parse().unwrap();
+    interpreter
+        .execute_all(&statements, &mut vec![])
+        .unwrap_err();
+    let files: Vec<_> = interpreter
+        .call_stack()
+        .iter()
+        .map(|frame| frame.file.as_deref())
+        .collect();
+    // `check` is called from the unnamed source that defined `outer`.
+    assert_eq!(files, [Some(Path::new("script.lox")), None]);
+    let frames: Vec<_> = interpreter
+        .call_stack()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(frames, ["at outer (script.lox:1)", "at check (line 3)"]);
+}
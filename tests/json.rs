@@ -0,0 +1,30 @@
+#![cfg(feature = "serde_json")]
+use lox::interpret::Value;
+use serde_json::json;
+
+#[test]
+fn converts_values_to_json() {
+    assert_eq!(serde_json::Value::from(Value::Nil), json!(null));
+    assert_eq!(serde_json::Value::from(Value::Boolean(true)), json!(true));
+    assert_eq!(serde_json::Value::from(Value::Number(1.5)), json!(1.5));
+    assert_eq!(
+        serde_json::Value::from(Value::Number(f64::NAN)),
+        json!(null)
+    );
+    assert_eq!(
+        serde_json::Value::from(Value::String("lox".into())),
+        json!("lox")
+    );
+}
+
+#[test]
+fn converts_json_to_values() {
+    assert_eq!(Value::try_from(json!(null)), Ok(Value::Nil));
+    assert_eq!(Value::try_from(json!(false)), Ok(Value::Boolean(false)));
+    assert_eq!(Value::try_from(json!(42)), Ok(Value::Number(42.0)));
+    assert_eq!(
+        Value::try_from(json!("lox")),
+        Ok(Value::String("lox".into()))
+    );
+    assert_eq!(Value::try_from(json!([1, 2])), Err(json!([1, 2])));
+}
@@ -0,0 +1,413 @@
+use std::path::Path;
+use std::time::Duration;
+
+use lox::error::Error;
+use lox::interpret::Value;
+use lox::{Config, Lox};
+
+#[test]
+fn sessions_keep_variables_between_runs() {
+    let mut lox = Lox::new();
+    lox.run_string("var a = 40; fun add(x) { return a + x; }")
+        .unwrap();
+    assert_eq!(lox.eval("add(2)").unwrap(), Value::Number(42.0));
+    assert_eq!(lox.eval("a = 1; print a;").unwrap(), Value::Nil);
+    assert_eq!(lox.eval("a").unwrap(), Value::Number(1.0));
+    assert!(matches!(
+        lox.eval("b"),
+        Err(Error::UndefinedVariable { .. })
+    ));
+}
+
+#[test]
+fn run_string_returns_runtime_errors_with_its_warnings() {
+    let mut warnings = Vec::new();
+    let result = lox::run_string(
+        "var x = 1; { var x = 2; } print nope;".to_string(),
+        &mut warnings,
+    );
+    assert!(matches!(result, Err(Error::UndefinedVariable { .. })));
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn files_resolve_imports_next_to_them() {
+    let mut lox = Lox::new();
+    lox.run_file(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/modules/main.lox"))
+        .unwrap();
+    assert_eq!(lox.eval("sixteen").unwrap(), Value::Number(16.0));
+}
+
+#[test]
+fn the_config_sets_limits_and_linting() {
+    let source = "var x = 1; { var x = 2; }";
+    let mut lox = Lox::new();
+    lox.run_string(source).unwrap();
+    let warnings = lox.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].to_string(),
+        "[line 1] Warning: Variable 'x' shadows outer declaration at line 1."
+    );
+
+    let mut lox = Lox::with_config(Config {
+        max_string_length: 4,
+        timeout: Some(Duration::from_millis(20)),
+        lint: false,
+    });
+    lox.run_string(source).unwrap();
+    assert!(lox.take_warnings().is_empty());
+    assert!(matches!(
+        lox.eval("\"abc\" + \"de\""),
+        Err(Error::ResourceLimit { max: 4, .. })
+    ));
+    assert!(matches!(
+        lox.run_string("while (true) {}"),
+        Err(Error::Timeout)
+    ));
+
+    // The timeout covers each run, not the life of the session.
+    lox.run_string("fun one() { return 1; }").unwrap();
+    std::thread::sleep(Duration::from_millis(40));
+    assert_eq!(lox.eval("one()").unwrap(), Value::Number(1.0));
+
+    // A lone expression is a run of its own.
+    lox.run_string("fun spin() { while (true) {} }").unwrap();
+    assert!(matches!(lox.eval("spin()"), Err(Error::Timeout)));
+}
+
+#[test]
+fn as_converts_between_types() {
+    let mut lox = Lox::new();
+    assert_eq!(
+        lox.eval("\" 42 \" as number + 1").unwrap(),
+        Value::Number(43.0)
+    );
+    assert_eq!(
+        lox.eval("-1.5 as string").unwrap(),
+        Value::String("-1.5".into())
+    );
+    assert_eq!(
+        lox.eval("nil as string").unwrap(),
+        Value::String("nil".into())
+    );
+    assert_eq!(
+        lox.eval("\"hi\" as string").unwrap(),
+        Value::String("hi".into())
+    );
+    assert_eq!(lox.eval("0 as bool").unwrap(), Value::Boolean(true));
+    assert_eq!(
+        lox.eval("2 * 3 as string as number").unwrap(),
+        Value::Number(6.0)
+    );
+
+    assert_eq!(
+        lox.eval("\"hello\" as number").unwrap_err().to_string(),
+        "[line 1] Type error: Cannot convert string \"hello\" to number."
+    );
+    assert!(lox.eval("true as number").is_err());
+    assert!(lox.eval("1 as integer").is_err());
+
+    lox.run_string("var as = 1;").unwrap();
+    assert_eq!(lox.eval("as as string").unwrap(), Value::String("1".into()));
+}
+
+#[test]
+fn run_options_can_stop_after_parsing() {
+    let path = std::env::temp_dir().join(format!("lox-run-options-{}.lox", std::process::id()));
+    std::fs::write(&path, "print missing;").unwrap();
+    let check = lox::RunOptions {
+        parse_only: true,
+        ..Default::default()
+    };
+    let result = lox::run_file(&path, check);
+    std::fs::write(&path, "print (missing;").unwrap();
+    let err = lox::run_file(&path, check).unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    result.unwrap();
+    assert_eq!(err.phase(), lox::error::Phase::Parse);
+}
+
+#[test]
+fn a_random_seed_repeats_the_same_numbers() {
+    let draw = |lox: &mut Lox| -> Vec<Value> {
+        (0..5)
+            .flat_map(|_| {
+                [
+                    lox.eval("random()").unwrap(),
+                    lox.eval("random_int(1, 6)").unwrap(),
+                ]
+            })
+            .collect()
+    };
+    let mut first = Lox::new().with_random_seed(42);
+    let mut second = Lox::new().with_random_seed(42);
+    let numbers = draw(&mut first);
+    assert_eq!(numbers, draw(&mut second));
+    assert_ne!(numbers, draw(&mut Lox::new().with_random_seed(43)));
+
+    for pair in numbers.chunks(2) {
+        let fraction = pair[0].as_number().unwrap();
+        assert!((0.0..1.0).contains(&fraction));
+        let roll = pair[1].as_number().unwrap();
+        assert!((1.0..=6.0).contains(&roll) && roll.fract() == 0.0);
+    }
+
+    assert_eq!(first.eval("random_int(3, 3)").unwrap(), Value::Number(3.0));
+    assert_eq!(
+        first.eval("random_int(2.5, 3.5)").unwrap(),
+        Value::Number(3.0)
+    );
+    for (source, message) in [
+        (
+            "random_int(5, 1)",
+            "[line 1] Type error: No integer lies between 5 and 1.",
+        ),
+        (
+            "random_int(-1/0, 1/0)",
+            "[line 1] Type error: Expected finite bounds but got -inf and inf.",
+        ),
+        (
+            "random_int(1, \"6\")",
+            "[line 1] Type error: Expected number but got string.",
+        ),
+    ] {
+        assert_eq!(first.eval(source).unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn random_int_stays_within_its_bounds() {
+    // Bounds are checked before drawing, so a failed call leaves the
+    // sequence where it was.
+    let mut first = Lox::new().with_random_seed(7);
+    let mut second = Lox::new().with_random_seed(7);
+    assert!(first.eval("random_int(5, 1)").is_err());
+    assert_eq!(
+        first.eval("random()").unwrap(),
+        second.eval("random()").unwrap()
+    );
+
+    // Near 2^53, adding one to the highest draw no longer makes a new
+    // number, and scaling a fraction could land on it.
+    let mut lox = Lox::new().with_random_seed(1);
+    let high = 2f64.powi(53);
+    for (source, low, high) in [
+        (
+            "random_int(9007199254740990, 9007199254740992)",
+            high - 2.0,
+            high,
+        ),
+        ("random_int(0, 9007199254740992)", 0.0, high),
+        (
+            "random_int(-1000000000000000000000000000000, 1000000000000000000000000000000)",
+            -1e30,
+            1e30,
+        ),
+        (
+            "random_int(-1000000000000000000000000000000000000000000000000000000000000, 1000000000000000000000000000000000000000000000000000000000000)",
+            -1e60,
+            1e60,
+        ),
+        (
+            "random_int(-179769313486231570814527423731704356798070567525844996598917476803157260780028538760589558632766878171540458953514382464234321326889464182768467546703537516986049910576551282076245490090389328944075868508455133942304583236903222948165808559332123348274797826204144723168738177180919299881250404026184124858368, 179769313486231570814527423731704356798070567525844996598917476803157260780028538760589558632766878171540458953514382464234321326889464182768467546703537516986049910576551282076245490090389328944075868508455133942304583236903222948165808559332123348274797826204144723168738177180919299881250404026184124858368)",
+            -f64::MAX,
+            f64::MAX,
+        ),
+    ] {
+        for _ in 0..100 {
+            let n = lox.eval(source).unwrap().as_number().unwrap();
+            assert!(
+                (low..=high).contains(&n) && n.fract() == 0.0,
+                "{source}: {n}"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regexps_match_and_replace_text() {
+    let mut lox = Lox::new();
+    lox.run_string(r#"var digits = regexp("[0-9]+");"#).unwrap();
+    assert_eq!(
+        lox.eval(r#""order 66, aisle 7".match(digits)(0)"#).unwrap(),
+        Value::String("66".into())
+    );
+    assert_eq!(lox.eval(r#""none".match(digits)"#).unwrap(), Value::Nil);
+    assert_eq!(
+        lox.eval(r##""a1b22c".replace(digits, "#")"##).unwrap(),
+        Value::String("a#b#c".into())
+    );
+    assert_eq!(
+        lox.eval(r#""2024-01".replace(regexp("""(\d+)-(\d+)"""), "$2/$1")"#)
+            .unwrap(),
+        Value::String("01/2024".into())
+    );
+    assert_eq!(
+        lox.eval(r#""a.b".replace(".", "-")"#).unwrap(),
+        Value::String("a-b".into())
+    );
+    assert_eq!(
+        lox.eval(r#"digits == regexp("[0-9]+")"#).unwrap(),
+        Value::Boolean(true)
+    );
+
+    lox.run_string(r#"var date = "on 2024-01".match(regexp("([0-9]+)-([0-9]+)?"));"#)
+        .unwrap();
+    assert_eq!(
+        lox.eval("date(0)").unwrap(),
+        Value::String("2024-01".into())
+    );
+    assert_eq!(lox.eval("date(2)").unwrap(), Value::String("01".into()));
+    assert_eq!(
+        lox.eval(r#""on 2024-".match(regexp("([0-9]+)-([0-9]+)?"))(2)"#)
+            .unwrap(),
+        Value::Nil
+    );
+    assert_eq!(
+        lox.eval("date(3)").unwrap_err().to_string(),
+        "[line 1] Type error: No group 3 in a match with 2 groups."
+    );
+
+    let err = lox.eval("\n regexp(\"(\")").unwrap_err();
+    assert!(matches!(err, Error::NativeError { line: 2, .. }));
+    assert_eq!(
+        lox.eval(r#""1".match("1")"#).unwrap_err().to_string(),
+        "[line 1] Type error: Expected regexp but got string."
+    );
+    assert_eq!(
+        lox.eval(r#""1".replace(1, "2")"#).unwrap_err().to_string(),
+        "[line 1] Type error: Expected string or regexp but got number."
+    );
+}
+
+#[test]
+fn strings_have_methods() {
+    let mut lox = Lox::new();
+    let s = |s: &str| Value::String(s.into());
+    assert_eq!(lox.eval("\"héllo\".len()").unwrap(), Value::Number(5.0));
+    assert_eq!(
+        lox.eval("\"Hi\".upper() + \"Hi\".lower()").unwrap(),
+        s("HIhi")
+    );
+    assert_eq!(lox.eval("\"  pad \".trim()").unwrap(), s("pad"));
+    assert_eq!(
+        lox.eval("\"lox.rs\".ends_with(\".rs\") and \"lox\".starts_with(\"l\")")
+            .unwrap(),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        lox.eval("\"team\".contains(\"I\")").unwrap(),
+        Value::Boolean(false)
+    );
+    assert_eq!(
+        lox.eval("\"a-b-c\".replace(\"-\", \"+\")").unwrap(),
+        s("a+b+c")
+    );
+    assert_eq!(lox.eval("\"héllo\".slice(1, 3)").unwrap(), s("él"));
+
+    lox.run_string("var upper = \"abc\".upper;").unwrap();
+    assert_eq!(lox.eval("upper()").unwrap(), s("ABC"));
+
+    assert_eq!(
+        lox.eval("\"abc\".slice(2, 4)").unwrap_err().to_string(),
+        "[line 1] Type error: Cannot slice 2 to 4 from a string of length 3."
+    );
+    assert_eq!(
+        lox.eval("\"abc\".size()").unwrap_err().to_string(),
+        "[line 1] Type error: Undefined property 'size'."
+    );
+    assert_eq!(
+        lox.eval("nil.len()").unwrap_err().to_string(),
+        "[line 1] Type error: Only strings and numbers have properties, not nil."
+    );
+    assert!(lox.eval("\"abc\".contains(1)").is_err());
+    assert!(lox.eval("\"abc\".len(1)").is_err());
+}
+
+#[test]
+fn numbers_have_methods() {
+    let mut lox = Lox::new();
+    assert_eq!(lox.eval("(3.7).floor()").unwrap(), Value::Number(3.0));
+    assert_eq!(lox.eval("(-3.2).ceil()").unwrap(), Value::Number(-3.0));
+    assert_eq!(lox.eval("(-5).abs()").unwrap(), Value::Number(5.0));
+    assert_eq!(
+        lox.eval("42.to_string() + (0.5).to_string()").unwrap(),
+        Value::String("420.5".into())
+    );
+    assert_eq!(
+        lox.eval("1.sqrt()").unwrap_err().to_string(),
+        "[line 1] Type error: Undefined property 'sqrt'."
+    );
+}
+
+#[test]
+fn bytes_hold_binary_data() {
+    let mut lox = Lox::new();
+    lox.run_string("var data = string_to_bytes(\"hi!\"); var alias = data;")
+        .unwrap();
+    assert_eq!(lox.eval("bytes_len(data)").unwrap(), Value::Number(3.0));
+    assert_eq!(
+        lox.eval("bytes_get(data, 0)").unwrap(),
+        Value::Number(104.0)
+    );
+    lox.run_string("bytes_set(alias, 2, 63);").unwrap();
+    assert_eq!(
+        lox.eval("bytes_to_string(data)").unwrap(),
+        Value::String("hi?".into())
+    );
+    assert_eq!(lox.eval("data").unwrap().to_string(), "<3 bytes>");
+    assert_eq!(
+        lox.eval("data == string_to_bytes(\"hi?\")").unwrap(),
+        Value::Boolean(true)
+    );
+    assert_eq!(
+        lox.eval("data")
+            .unwrap()
+            .to_hashable(4)
+            .unwrap_err()
+            .to_string(),
+        "[line 4] Type error: Bytes cannot be used as a key."
+    );
+
+    assert_eq!(
+        lox.eval("bytes_get(data, 3)").unwrap_err().to_string(),
+        "[line 1] Type error: Index 3 is out of range for 3 bytes."
+    );
+    assert!(lox.eval("bytes_set(data, 0, 256)").is_err());
+    // A clone has bytes of its own.
+    lox.run_string("var copy = clone(data); bytes_set(copy, 0, 72);")
+        .unwrap();
+    assert_eq!(
+        lox.eval("bytes_to_string(copy) + bytes_to_string(data)")
+            .unwrap(),
+        Value::String("Hi?hi?".into())
+    );
+    assert_eq!(lox.eval("clone(42)").unwrap(), Value::Number(42.0));
+
+    lox.run_string("bytes_set(data, 0, 255);").unwrap();
+    assert!(matches!(
+        lox.eval("bytes_to_string(data)").unwrap_err(),
+        Error::NativeError { .. }
+    ));
+}
+
+#[cfg(feature = "io")]
+#[test]
+fn files_can_be_read_as_bytes() {
+    let path = std::env::temp_dir().join(format!("lox-bytes-{}.bin", std::process::id()));
+    std::fs::write(&path, [0, 1, 255]).unwrap();
+    let mut lox = Lox::new();
+    let source = format!(
+        "bytes_get(read_file_bytes({:?}), 2)",
+        path.display().to_string()
+    );
+    let result = lox.eval(&source);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap(), Value::Number(255.0));
+    let err = lox.eval("read_file_bytes(\"/no/such/file\")").unwrap_err();
+    assert!(matches!(err, Error::NativeError { line: 1, .. }));
+    assert_eq!(err.phase(), lox::error::Phase::Runtime);
+}
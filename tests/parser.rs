@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use lox::error::Error;
+use lox::scanner::Scanner;
+use lox::syntax::{Parser, PrattParser, Stmt};
+use proptest::prelude::*;
+
+fn parser(source: &str) -> Parser {
+    Parser::new(Scanner::new(source.to_string()).scan_tokens().unwrap())
+}
+
+#[test]
+fn error_reporter_sees_every_error() {
+    let reported = Rc::new(RefCell::new(vec![]));
+    let sink = Rc::clone(&reported);
+    let statements = parser("var = 1;\nprint 2;\nprint (3;\nvar ok = 4;")
+        .with_error_reporter(move |e| sink.borrow_mut().push(e.to_string()))
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        *reported.borrow(),
+        [
+            "[line 1] Error at '=': Expected variable name.",
+            "[line 3] Error at ';': Expected ')' after expression.",
+        ]
+    );
+    assert_eq!(statements.len(), 2);
+}
+
+#[test]
+fn synchronizing_keeps_the_next_statement() {
+    let reported = Rc::new(RefCell::new(vec![]));
+    let sink = Rc::clone(&reported);
+    let statements = parser(") print 1 var x = 2; ; print x;")
+        .with_error_reporter(move |e| sink.borrow_mut().push(e.to_string()))
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        *reported.borrow(),
+        [
+            "[line 1] Error at ')': Expected expression.",
+            "[line 1] Error at 'var': Expected ';' after value.",
+            "[line 1] Error at ';': Expected expression.",
+        ]
+    );
+    assert!(matches!(
+        statements.as_slice(),
+        [Stmt::Var { .. }, Stmt::Print(_)]
+    ));
+}
+
+#[test]
+fn without_a_reporter_the_first_error_fails_the_parse() {
+    let err = parser("var = 1;\nprint (3;").parse().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[line 1] Error at '=': Expected variable name."
+    );
+}
+
+#[test]
+fn remaining_tokens_start_at_the_error() {
+    let mut parser = parser("print 1;\nprint (2;\nprint 3;");
+    assert!(parser.parse().is_err());
+
+    assert_eq!(parser.current_position(), 6);
+    let remaining: Vec<&str> = parser
+        .remaining_tokens()
+        .iter()
+        .map(|token| token.lexeme())
+        .collect();
+    assert_eq!(remaining, [";", "print", "3", ";", ""]);
+    assert_eq!(parser.tokens().len(), 11);
+    assert_eq!(parser.tokens()[parser.current_position()].lexeme(), ";");
+}
+
+#[test]
+fn parse_at_the_crate_root() {
+    let statements = lox::parse("var a = 1; print a;").unwrap();
+    assert_eq!(statements.len(), 2);
+    assert!(lox::parse("var = 1;").is_err());
+
+    assert_eq!(
+        lox::parse_expr("1 + 2 * 3").unwrap().to_string(),
+        "(+ 1 (* 2 3))"
+    );
+    assert!(lox::parse_expr("1; 2").is_err());
+}
+
+#[test]
+fn declarations_are_not_statements() {
+    for (source, found) in [
+        ("if (true) var y = 1;", "'var'"),
+        ("while (false) fun f() {}", "'fun'"),
+    ] {
+        let err = lox::parse(source).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("[line 1] Error at {found}: Expected expression."),
+            "{source}"
+        );
+    }
+    assert!(lox::parse("if (true) { var y = 1; } else print 2;").is_ok());
+}
+
+#[test]
+fn exports_are_only_allowed_at_the_top_level() {
+    assert!(matches!(
+        lox::parse("export var x = 1; export fun f() {} export default x;")
+            .unwrap()
+            .as_slice(),
+        [Stmt::Export(_), Stmt::Export(_), Stmt::ExportDefault(_)]
+    ));
+    // `default` is still an ordinary name outside of `export default`.
+    assert!(lox::parse("var default = 1; export var copy = default;").is_ok());
+
+    for source in ["{ export var x = 1; }", "export print 1;", "export x;"] {
+        assert!(
+            matches!(lox::parse(source), Err(Error::UnexpectedToken { .. })),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn syntax_trees_can_be_cloned() {
+    let expr: lox::syntax::Expr = "(a + 1) * f(b, 2)".parse().unwrap();
+    assert_eq!(expr.clone().to_string(), expr.to_string());
+
+    let statements = lox::parse("fun f() { return 1; } var x = f();").unwrap();
+    match (&statements[0], &statements.clone()[0]) {
+        (Stmt::Function(original), Stmt::Function(copy)) => assert!(Rc::ptr_eq(original, copy)),
+        other => panic!("expected function declarations, got {other:?}"),
+    }
+}
+
+/// Parses `source` with both expression parsers, which must agree.
+fn check_pratt(source: &str) -> Result<(), TestCaseError> {
+    let Ok(tokens) = Scanner::new(source.to_string()).scan_tokens() else {
+        return Ok(());
+    };
+    let descent = Parser::new(tokens.clone()).parse_expression();
+    let pratt = PrattParser::new(tokens).parse_expression();
+    match (descent, pratt) {
+        (Ok(descent), Ok(pratt)) => {
+            prop_assert_eq!(format!("{descent:?}"), format!("{pratt:?}"), "{}", source)
+        }
+        (Err(descent), Err(pratt)) => {
+            prop_assert_eq!(descent.to_string(), pratt.to_string(), "{}", source)
+        }
+        (descent, pratt) => prop_assert!(false, "{source:?}: {descent:?} vs {pratt:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn pratt_parser_matches_recursive_descent() {
+    for source in [
+        "1 + 2 * 3 - 4 / 5",
+        "-a.b",
+        "!!true == false != nil",
+        "a = b = c or d and e",
+        "f(1, (2, 3))(g)",
+        "(1, 2, 3)",
+        "-f() < 2 >= 3",
+        "a + b = c",
+        "(a) = 1",
+        "fun (a, b) { return a + b; }(1, 2)",
+        "fun x",
+        "-a as number * b as string as bool",
+        "as as as",
+        "a.b(c).d",
+        "f().g = 1",
+        "a.1",
+        "a as 1",
+        "x as number(1)",
+        "x as number.foo",
+        "a as string.b(c)",
+        "1 +",
+        "f(1,",
+        "(1",
+        "1 2",
+    ] {
+        check_pratt(source).unwrap();
+    }
+}
+
+proptest! {
+    #[test]
+    fn pratt_parser_matches_on_arbitrary_input(
+        source in r#"([a-c0-9 (),!=<>+*/.-]|or|and|nil|as|number|"s"){0,24}"#
+    ) {
+        check_pratt(&source)?;
+    }
+}
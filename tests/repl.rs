@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use lox::error::Error;
+use lox::interpret::{Interpreter, Value};
+use lox::repl::Repl;
+
+#[test]
+fn expressions_produce_values_and_statements_do_not() {
+    let mut repl = Repl::new();
+    assert!(matches!(repl.eval_line("var a = 40;"), Ok(None)));
+    assert_eq!(repl.eval_line("a + 2").unwrap(), Some(Value::Number(42.0)));
+    assert!(repl.eval_line("print b;").is_err());
+    assert_eq!(repl.history(), ["var a = 40;", "a + 2", "print b;"]);
+}
+
+#[test]
+fn reset_forgets_variables() {
+    let mut repl = Repl::new();
+    repl.eval_line("var a = 1;").unwrap();
+    assert_eq!(repl.state().values().len(), 1);
+
+    repl.reset();
+    assert!(repl.state().values().is_empty());
+    assert!(repl.eval_line("a").is_err());
+}
+
+#[test]
+fn state_iterates_inner_scopes_first() {
+    let mut repl = Repl::new();
+    repl.eval_line("var b = 2; var a = 1; var clock = 0;")
+        .unwrap();
+
+    let bindings: Vec<_> = repl.state().iter().collect();
+    assert_eq!(
+        bindings[..3],
+        [
+            ("a".to_string(), Value::Number(1.0)),
+            ("b".to_string(), Value::Number(2.0)),
+            ("clock".to_string(), Value::Number(0.0)),
+        ]
+    );
+    let builtins: Vec<_> = bindings[3..]
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    // The global `clock` hides the native one.
+    let mut expected = vec![
+        "bytes_get",
+        "bytes_len",
+        "bytes_set",
+        "bytes_to_string",
+        "clone",
+        "random",
+        "random_int",
+        "string_to_bytes",
+    ];
+    #[cfg(feature = "regex")]
+    expected.push("regexp");
+    #[cfg(all(feature = "io", not(target_arch = "wasm32")))]
+    expected.push("read_file_bytes");
+    expected.sort_unstable();
+    assert_eq!(builtins, expected);
+}
+
+#[test]
+fn incomplete_input_is_recoverable() {
+    let mut repl = Repl::new();
+    for partial in ["fun add(a, b) {", "print (1 +", "var s = \"open"] {
+        let err = repl.eval_line(partial).unwrap_err();
+        assert!(err.is_recoverable(), "{partial}: {err}");
+    }
+    let err = repl.eval_line("print (1;").unwrap_err();
+    assert!(!err.is_recoverable());
+    assert_eq!(
+        err.to_string(),
+        "[<repl>:1:9] Error at ';': Expected ')' after expression."
+    );
+    assert_eq!(repl.history(), ["print (1;"]);
+
+    repl.eval_line("fun add(a, b) {\n  return a + b;\n}")
+        .unwrap();
+    assert_eq!(
+        repl.eval_line("add(1, 2)").unwrap(),
+        Some(Value::Number(3.0))
+    );
+}
+
+#[test]
+fn expressions_are_time_limited_like_statements() {
+    let interpreter = Interpreter::new().with_timeout(Duration::from_millis(50));
+    let mut repl = Repl::with_interpreter(interpreter);
+    repl.eval_line("fun spin() { while (true) {} }").unwrap();
+    assert!(matches!(repl.eval_line("spin();"), Err(Error::Timeout)));
+    assert!(matches!(repl.eval_line("spin()"), Err(Error::Timeout)));
+}
@@ -0,0 +1,493 @@
+use std::path::Path;
+
+use lox::interpret::Interpreter;
+use lox::scanner::{Scanner, TokenType};
+use lox::syntax::Parser;
+use proptest::prelude::*;
+
+/// Skips the whitespace and comments the scanner discards between tokens.
+fn skip_ignored(source: &str, mut pos: usize) -> usize {
+    loop {
+        let rest = &source[pos..];
+        if rest.starts_with([' ', '\r', '\t', '\n']) {
+            pos += 1;
+        } else if rest.starts_with("//") {
+            pos += rest.find('\n').unwrap_or(rest.len());
+        } else {
+            return pos;
+        }
+    }
+}
+
+fn check_tokens(source: String) -> Result<(), TestCaseError> {
+    let Ok(tokens) = Scanner::new(source.clone()).scan_tokens() else {
+        return Ok(());
+    };
+
+    let (eof, tokens) = tokens.split_last().expect("scanner returned no tokens");
+    prop_assert_eq!(eof.ty(), &TokenType::Eof);
+    prop_assert!(eof.line() >= 1);
+
+    // Every byte of the source is either part of a lexeme or skipped.
+    let mut pos = 0;
+    for token in tokens {
+        prop_assert!(token.line() >= 1);
+        pos = skip_ignored(&source, pos);
+        prop_assert_eq!(token.span(), (pos, pos + token.lexeme().len()));
+        prop_assert!(
+            source[pos..].starts_with(token.lexeme()),
+            "lexeme {:?} not found at byte {} of {:?}",
+            token.lexeme(),
+            pos,
+            source
+        );
+        pos += token.lexeme().len();
+    }
+    prop_assert_eq!(skip_ignored(&source, pos), source.len());
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn scans_arbitrary_strings(source in any::<String>()) {
+        check_tokens(source)?;
+    }
+
+    #[test]
+    fn scans_lox_like_strings(source in r#"[a-z0-9_éπ (){},.;+*/!=<>"\t\r\n-]{0,64}"#) {
+        check_tokens(source)?;
+    }
+}
+
+#[test]
+fn classifies_tokens_for_highlighting() {
+    use lox::scanner::{classify_tokens, TokenClass::*};
+
+    let source = "var x = \"s\"; // note\n  print (x + 1.5);";
+    let spans: Vec<_> = classify_tokens(source)
+        .into_iter()
+        .map(|(class, start, end)| (class, &source[start..end]))
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            (Keyword, "var"),
+            (Whitespace, " "),
+            (Identifier, "x"),
+            (Whitespace, " "),
+            (Operator, "="),
+            (Whitespace, " "),
+            (String, "\"s\""),
+            (Punctuation, ";"),
+            (Whitespace, " "),
+            (Comment, "// note"),
+            (Whitespace, "\n  "),
+            (Keyword, "print"),
+            (Whitespace, " "),
+            (Punctuation, "("),
+            (Identifier, "x"),
+            (Whitespace, " "),
+            (Operator, "+"),
+            (Whitespace, " "),
+            (Number, "1.5"),
+            (Punctuation, ")"),
+            (Punctuation, ";"),
+        ]
+    );
+}
+
+fn string_literal(source: &str) -> lox::error::Result<String> {
+    let tokens = Scanner::new(source.to_string()).scan_tokens()?;
+    match tokens[0].ty() {
+        TokenType::String(s) => Ok(s.clone()),
+        ty => panic!("expected a string, got {ty:?}"),
+    }
+}
+
+#[test]
+fn decodes_escape_sequences() {
+    assert_eq!(
+        string_literal(r#""\u{48}\u{65}\u{6C}\u{6C}\u{6F}""#).unwrap(),
+        "Hello"
+    );
+    assert_eq!(string_literal(r#""é\u{1F600}""#).unwrap(), "é😀");
+    assert_eq!(
+        string_literal(r#""a\tb\n\"c\"\\""#).unwrap(),
+        "a\tb\n\"c\"\\"
+    );
+
+    let hello: lox::syntax::Expr =
+        r#""\u{48}\u{65}\u{6C}\u{6C}\u{6F}" == "Hello""#.parse().unwrap();
+    assert_eq!(
+        lox::interpret::evaluate(hello, &mut vec![]).unwrap(),
+        lox::interpret::Value::Boolean(true)
+    );
+}
+
+#[test]
+fn rejects_invalid_escape_sequences() {
+    for source in [
+        r#""\q""#,
+        r#""\u{}""#,
+        r#""\u{D800}""#,
+        r#""\u{110000}""#,
+        r#""\u{1234567}""#,
+        r#""\u12""#,
+        r#""\u{41""#,
+        r#""\"#,
+    ] {
+        assert!(string_literal(source).is_err(), "{source}");
+    }
+}
+
+#[test]
+fn scans_triple_quoted_strings() {
+    let source = "\"\"\"\nSELECT *\n  FROM t\\n\"\"\" 1";
+    let tokens = Scanner::new(source.to_string()).scan_tokens().unwrap();
+    assert_eq!(
+        tokens[0].ty(),
+        &TokenType::RawString("SELECT *\n  FROM t\\n".to_string())
+    );
+    assert_eq!(tokens[1].line(), 3);
+
+    assert!(Scanner::new("\"\"\"open".to_string())
+        .scan_tokens()
+        .is_err());
+}
+
+#[test]
+fn errors_in_files_name_the_file_and_column() {
+    let path = std::env::temp_dir().join(format!("lox-scanner-{}.lox", std::process::id()));
+    std::fs::write(&path, "var a = 1;\nprint a # 2;").unwrap();
+    let err = Scanner::new_with_file(&path)
+        .unwrap()
+        .scan_tokens()
+        .unwrap_err();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        err.to_string(),
+        format!("[{}:2:9] Error: Unexpected character.", path.display())
+    );
+}
+
+#[test]
+fn named_sources_name_their_errors() {
+    let scan = |source: &str| {
+        Scanner::new(source.to_string())
+            .with_source_name("<stdin>")
+            .scan_tokens()
+    };
+    let err = scan("print 1;\n  @").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[<stdin>:2:3] Error: Unexpected character."
+    );
+    assert_eq!(err.source_name(), Some(Path::new("<stdin>")));
+
+    let tokens = scan("print (1;").unwrap();
+    assert_eq!(tokens[0].source_name(), Some(Path::new("<stdin>")));
+    let err = Parser::new(tokens).parse().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[<stdin>:1:9] Error at ';': Expected ')' after expression."
+    );
+
+    let statements = Parser::new(scan("var a;\nprint -a;").unwrap())
+        .parse()
+        .unwrap();
+    let err = Interpreter::new()
+        .execute_all(&statements, &mut vec![])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "[<stdin>:2] Type error: Expected number but got nil."
+    );
+
+    // Naming a source does not change what kind of error it raises.
+    let run = |mut scanner: Scanner| -> lox::error::Result<()> {
+        let statements = Parser::new(scanner.scan_tokens()?).parse()?;
+        Interpreter::new().execute_all(&statements, &mut vec![])
+    };
+    for source in ["\"open", "@", "print (1;", "print -nil;"] {
+        let scanner = Scanner::new(source.to_string());
+        let unnamed = run(scanner).unwrap_err();
+        let scanner = Scanner::new(source.to_string()).with_source_name("<stdin>");
+        let named = run(scanner).unwrap_err();
+        assert_eq!(named.phase(), unnamed.phase(), "{source}");
+        assert_eq!(named.is_recoverable(), unnamed.is_recoverable(), "{source}");
+    }
+    assert!(scan("\"open").unwrap_err().is_recoverable());
+}
+
+#[test]
+fn token_kind_helpers() {
+    let tokens = Scanner::new("var x = nil; (1 != \"a\")".to_string())
+        .scan_tokens()
+        .unwrap();
+    let kinds: Vec<_> = tokens
+        .iter()
+        .map(|t| {
+            (
+                t.lexeme(),
+                t.is_keyword(),
+                t.is_literal(),
+                t.is_operator(),
+                t.is_punctuation(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("var", true, false, false, false),
+            ("x", false, false, false, false),
+            ("=", false, false, true, false),
+            ("nil", true, true, false, false),
+            (";", false, false, false, true),
+            ("(", false, false, false, true),
+            ("1", false, true, false, false),
+            ("!=", false, false, true, false),
+            ("\"a\"", false, true, false, false),
+            (")", false, false, false, true),
+            ("", false, false, false, false),
+        ]
+    );
+}
+
+#[test]
+fn scan_at_the_crate_root() {
+    let tokens = lox::scan("print 1;").unwrap();
+    let lexemes: Vec<_> = tokens.iter().map(|t| t.lexeme()).collect();
+    assert_eq!(lexemes, ["print", "1", ";", ""]);
+    assert!(lox::scan("@").is_err());
+}
+
+#[test]
+fn reset_rescans_the_same_tokens() {
+    let mut scanner = Scanner::new("var a = \"x\ny\";\nprint a;".to_string());
+    let first = describe_tokens(&scanner.scan_tokens().unwrap());
+    scanner.reset();
+    let second = describe_tokens(&scanner.scan_tokens().unwrap());
+    assert_eq!(first, second);
+}
+
+fn describe_tokens(tokens: &[lox::scanner::Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|t| format!("{t} {} {} {:?}", t.line(), t.column(), t.span()))
+        .collect()
+}
+
+/// Applies an edit with `rescan_range` and checks the result against
+/// scanning the edited source from scratch.
+fn check_rescan(source: &str, lines: (usize, usize), new_text: &str) -> Result<(), TestCaseError> {
+    let mut scanner = Scanner::new(source.to_string());
+    let _ = scanner.scan_tokens();
+    let rescanned = scanner.rescan_range(lines.0, lines.1, new_text);
+
+    let source_lines: Vec<&str> = source.split('\n').collect();
+    let edited: String = source_lines[..lines.0 - 1]
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .chain([new_text.to_string(), source_lines[lines.1..].join("\n")])
+        .collect();
+    let expected = Scanner::new(edited.clone()).scan_tokens();
+
+    match (rescanned, expected) {
+        (Ok(actual), Ok(expected)) => prop_assert_eq!(
+            describe_tokens(&actual),
+            describe_tokens(&expected),
+            "edited source {:?}",
+            edited
+        ),
+        (Err(actual), Err(expected)) => prop_assert_eq!(actual.to_string(), expected.to_string()),
+        (actual, expected) => prop_assert!(
+            false,
+            "{:?}: got {:?}, expected {:?}",
+            edited,
+            actual.map(|t| describe_tokens(&t)),
+            expected.map(|t| describe_tokens(&t))
+        ),
+    }
+    Ok(())
+}
+
+#[test]
+fn rescans_edited_lines() {
+    let source = "var a = 1;\nvar b = \"two\nlines\";\nprint a + b;";
+    check_rescan(source, (1, 1), "var a = 10;\n").unwrap();
+    check_rescan(source, (3, 3), "print a;\n").unwrap();
+    check_rescan(source, (2, 2), "var b = \"\n").unwrap();
+    check_rescan(source, (2, 1), "// inserted\n").unwrap();
+    check_rescan(source, (4, 4), "").unwrap();
+}
+
+#[test]
+fn rescanning_lines_outside_the_source_fails() {
+    let mut scanner = Scanner::new("print 1;\nprint 2;".to_string());
+    scanner.scan_tokens().unwrap();
+    for (start, end) in [(0, 1), (3, 3), (2, 0)] {
+        assert!(matches!(
+            scanner.rescan_range(start, end, "print 3;"),
+            Err(lox::error::Error::LineRange { lines: 2, .. })
+        ));
+    }
+    assert_eq!(scanner.rescan_range(2, 2, "print 3;").unwrap().len(), 7);
+}
+
+proptest! {
+    #[test]
+    fn rescanning_matches_a_full_scan(
+        source in r#"[a-z0-9 (){};+"\n]{0,48}"#,
+        start in 1usize..8,
+        len in 0usize..3,
+        new_text in r#"[a-z0-9 (){};+"\n]{0,16}"#,
+    ) {
+        let line_count = source.split('\n').count();
+        let start = start.min(line_count);
+        let end = (start + len).saturating_sub(1).min(line_count);
+        check_rescan(&source, (start, end), &new_text)?;
+    }
+}
+
+#[test]
+fn token_types_have_display_names() {
+    let tokens = lox::scan("( >= foo \"s\" 1 while").unwrap();
+    let names: Vec<_> = tokens.iter().map(|t| t.ty().display_name()).collect();
+    assert_eq!(
+        names,
+        [
+            "'('",
+            "'>='",
+            "identifier",
+            "string literal",
+            "number",
+            "'while'",
+            "end of file"
+        ]
+    );
+}
+
+#[test]
+fn scan_into_reuses_the_buffer() {
+    let mut tokens = Vec::with_capacity(16);
+    for source in ["var a = 1;", "print a;"] {
+        Scanner::new(source.to_string())
+            .scan_into(&mut tokens)
+            .unwrap();
+        let expected = Scanner::new(source.to_string()).scan_tokens().unwrap();
+        assert_eq!(
+            tokens.iter().map(|t| t.lexeme()).collect::<Vec<_>>(),
+            expected.iter().map(|t| t.lexeme()).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(tokens.capacity(), 16);
+
+    assert!(Scanner::new("1 @".to_string())
+        .scan_into(&mut tokens)
+        .is_err());
+    assert_eq!(tokens.len(), 1);
+}
+
+#[test]
+fn peeking_does_not_consume_tokens() {
+    let mut scanner = Scanner::new("print // comment\n  x;".to_string());
+    let peeked = scanner.peek_token().unwrap().unwrap();
+    assert_eq!(peeked.lexeme(), "print");
+    assert_eq!(scanner.peek_token().unwrap().unwrap().lexeme(), "print");
+    assert_eq!(scanner.next_token().unwrap().unwrap().lexeme(), "print");
+
+    assert_eq!(scanner.peek_token().unwrap().unwrap().lexeme(), "x");
+    let rest: Vec<_> = scanner
+        .scan_tokens()
+        .unwrap()
+        .iter()
+        .map(|t| t.lexeme().to_string())
+        .collect();
+    assert_eq!(rest, ["x", ";", ""]);
+    assert!(scanner.next_token().unwrap().is_none());
+    assert!(scanner.peek_token().unwrap().is_none());
+}
+
+#[test]
+fn token_types_display_as_lox_source() {
+    let source = "(a != \"say \\\"hi\\\"\\n\") 42 1.5 class \"\"\"raw\"\"\"";
+    let tokens = lox::scan(source).unwrap();
+    let shown: Vec<_> = tokens.iter().map(|t| t.ty().to_string()).collect();
+    assert_eq!(
+        shown,
+        [
+            "(",
+            "a",
+            "!=",
+            "\"say \\\"hi\\\"\\n\"",
+            ")",
+            "42",
+            "1.5",
+            "class",
+            "\"\"\"raw\"\"\"",
+            ""
+        ]
+    );
+}
+
+proptest! {
+    #[test]
+    fn displayed_token_types_scan_back(source in r#"[a-z0-9 (){},.;+*/!=<>"\\\t\n-]{0,32}"#) {
+        let Ok(tokens) = lox::scan(&source) else {
+            return Ok(());
+        };
+        let shown: Vec<_> = tokens.iter().map(|t| t.ty().to_string()).collect();
+        let rescanned = lox::scan(&shown.join(" ")).unwrap();
+        prop_assert_eq!(
+            tokens.iter().map(|t| format!("{:?}", t.ty())).collect::<Vec<_>>(),
+            rescanned.iter().map(|t| format!("{:?}", t.ty())).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn all_errors_keeps_scanning() {
+    let (tokens, errors) = Scanner::new("var a = @;\nprint \"\\q\" # a;\n\"open".to_string())
+        .with_source_name("<stdin>")
+        .all_errors();
+    let errors: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errors,
+        [
+            "[<stdin>:1:9] Error: Unexpected character.",
+            "[<stdin>:2:7] Error: Invalid escape sequence.",
+            "[<stdin>:2:12] Error: Unexpected character.",
+            "[<stdin>:3] Error: Unterminated string.",
+        ]
+    );
+    let lexemes: Vec<_> = tokens.iter().map(|t| t.lexeme()).collect();
+    assert_eq!(lexemes, ["var", "a", "=", ";", "print", "a", ";", ""]);
+
+    let (tokens, errors) = Scanner::new("print 1;".to_string()).all_errors();
+    assert!(errors.is_empty());
+    assert_eq!(tokens.len(), 4);
+}
+
+#[test]
+fn shebang_lines_are_skipped() {
+    let tokens = lox::scan("#!/usr/bin/env lox\nprint 1;").unwrap();
+    let lexemes: Vec<_> = tokens.iter().map(|t| (t.lexeme(), t.line())).collect();
+    assert_eq!(lexemes, [("print", 2), ("1", 2), (";", 2), ("", 2)]);
+    assert!(lox::scan("#!lox").unwrap()[0].ty() == &TokenType::Eof);
+    assert!(lox::scan("print 1;\n#!lox").is_err());
+
+    let mut scanner = Scanner::new("#!lox\nx".to_string());
+    assert_eq!(scanner.next_token().unwrap().unwrap().lexeme(), "x");
+
+    let spans = lox::scanner::classify_tokens("#!lox\nx");
+    assert_eq!(
+        spans,
+        [
+            (lox::scanner::TokenClass::Comment, 0, 5),
+            (lox::scanner::TokenClass::Whitespace, 5, 6),
+            (lox::scanner::TokenClass::Identifier, 6, 7),
+        ]
+    );
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use lox::interpret::Value;
+
+#[test]
+fn hashable_values_canonicalize_numbers() {
+    let mut map = HashMap::new();
+    map.insert(Value::Number(f64::NAN).to_hashable(1).unwrap(), "nan");
+    map.insert(Value::Number(0.0).to_hashable(1).unwrap(), "zero");
+    map.insert(Value::String("0".into()).to_hashable(1).unwrap(), "string");
+    map.insert(Value::Nil.to_hashable(1).unwrap(), "nil");
+
+    let get = |value: Value| map.get(&value.to_hashable(1).unwrap()).copied();
+    assert_eq!(get(Value::Number(-f64::NAN)), Some("nan"));
+    assert_eq!(get(Value::Number(-0.0)), Some("zero"));
+    assert_eq!(get(Value::String("0".into())), Some("string"));
+    assert_eq!(get(Value::Nil), Some("nil"));
+    assert_eq!(get(Value::Boolean(false)), None);
+
+    let clock = lox::Lox::new().eval("clock").unwrap();
+    assert_eq!(
+        clock.to_hashable(2).unwrap_err().to_string(),
+        "[line 2] Type error: Functions cannot be used as a key."
+    );
+}
+
+#[test]
+fn debug_output_shows_the_lox_type() {
+    assert_eq!(
+        format!("{:?}", Value::String("hello".into())),
+        "\"hello\" (string)"
+    );
+    assert_eq!(format!("{:?}", Value::Number(42.0)), "42 (number)");
+    assert_eq!(format!("{:?}", Value::Boolean(true)), "true (boolean)");
+    assert_eq!(format!("{:?}", Value::Nil), "nil (nil)");
+}
+
+#[test]
+fn accessors_match_only_their_own_type() {
+    let values = [
+        Value::Number(1.5),
+        Value::String("hi".into()),
+        Value::Boolean(false),
+        Value::Nil,
+    ];
+    let numbers: Vec<_> = values.iter().map(Value::as_number).collect();
+    assert_eq!(numbers, [Some(1.5), None, None, None]);
+    let strings: Vec<_> = values.iter().map(Value::as_string).collect();
+    assert_eq!(strings, [None, Some("hi"), None, None]);
+    let bools: Vec<_> = values.iter().map(Value::as_bool).collect();
+    assert_eq!(bools, [None, None, Some(false), None]);
+    let nils: Vec<_> = values.iter().map(Value::is_nil).collect();
+    assert_eq!(nils, [false, false, false, true]);
+}
+
+#[test]
+fn arithmetic_operators_follow_lox_rules() {
+    let n = Value::Number;
+    let s = |s: &str| Value::String(s.into());
+    assert_eq!((n(1.0) + n(2.0)).unwrap(), n(3.0));
+    assert_eq!((s("ab") + s("cd")).unwrap(), s("abcd"));
+    assert_eq!((n(5.0) - n(2.0)).unwrap(), n(3.0));
+    assert_eq!((n(2.0) * n(4.0)).unwrap(), n(8.0));
+    assert_eq!((n(1.0) / n(4.0)).unwrap(), n(0.25));
+    assert_eq!((-n(1.0)).unwrap(), n(-1.0));
+    assert_eq!(!Value::Nil, Value::Boolean(true));
+
+    assert!((n(1.0) + s("a")).is_err());
+    assert!((s("a") - s("b")).is_err());
+    assert!((-Value::Nil).is_err());
+    assert_eq!(
+        (n(1.0) + Value::Nil).unwrap_err().to_string(),
+        "[line 0] Type error: Cannot add nil to number."
+    );
+}
+
+#[test]
+fn evaluates_against_an_environment() {
+    use lox::environment::Environment;
+    use lox::interpret::evaluate_with_environment;
+    use lox::syntax::Expr;
+
+    let mut environment = Environment::new();
+    environment.define("price", Value::Number(4.0));
+    environment.define("quantity", Value::Number(3.0));
+    environment.define("total", Value::Nil);
+
+    let expr: Expr = "total = price * quantity".parse().unwrap();
+    let value = evaluate_with_environment(expr, &mut environment).unwrap();
+    assert_eq!(value, Value::Number(12.0));
+    assert_eq!(environment.values()["total"], Value::Number(12.0));
+
+    let expr: Expr = "clock".parse().unwrap();
+    assert!(evaluate_with_environment(expr, &mut environment).is_err());
+    assert_eq!(environment.values()["price"], Value::Number(4.0));
+
+    // A lambda captures the scope, so the caller gets its variables back as
+    // a copy.
+    let expr: Expr = "total = fun () { return price; }".parse().unwrap();
+    let lambda = evaluate_with_environment(expr, &mut environment).unwrap();
+    assert_eq!(environment.values()["total"], lambda);
+    assert_eq!(environment.values()["quantity"], Value::Number(3.0));
+}
+
+#[test]
+fn only_values_of_the_same_type_are_ordered() {
+    let n = Value::Number;
+    let s = |s: &str| Value::String(s.into());
+    assert!(n(1.0) < n(2.0));
+    assert!(s("apple") < s("banana"));
+    assert!(Value::Boolean(false) < Value::Boolean(true));
+    assert_eq!(
+        Value::Nil.partial_cmp(&Value::Nil),
+        Some(std::cmp::Ordering::Equal)
+    );
+
+    assert_eq!(n(1.0).partial_cmp(&s("1")), None);
+    assert_eq!(n(f64::NAN).partial_cmp(&n(f64::NAN)), None);
+    assert_eq!(Value::Nil.partial_cmp(&n(0.0)), None);
+}
+
+#[test]
+fn lox_strings_are_unquoted() {
+    assert_eq!(Value::String("hi".into()).to_lox_string(), "hi");
+    assert_eq!(Value::String("hi".into()).to_string(), "\"hi\"");
+    assert_eq!(Value::Number(42.0).to_lox_string(), "42");
+    assert_eq!(Value::Number(0.5).to_lox_string(), "0.5");
+    assert_eq!(Value::Boolean(true).to_lox_string(), "true");
+    assert_eq!(Value::Nil.to_lox_string(), "nil");
+}
+
+#[test]
+fn deep_clones_share_no_bytes() {
+    let original = lox::Lox::new().eval("string_to_bytes(\"abc\")").unwrap();
+    let copy = original.deep_clone();
+    let Value::Bytes(bytes) = &copy else {
+        panic!("expected bytes but got {copy}");
+    };
+    bytes.borrow_mut()[0] = b'z';
+    assert_eq!(copy.to_string(), "<3 bytes>");
+    assert_ne!(original, copy);
+    assert_eq!(Value::Number(1.0).deep_clone(), Value::Number(1.0));
+    assert_eq!(Value::Nil.deep_clone(), Value::Nil);
+}
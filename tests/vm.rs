@@ -0,0 +1,44 @@
+use lox::compiler::Compiler;
+use lox::interpret::evaluate;
+use lox::syntax::Expr;
+use lox::vm::Vm;
+
+const EXPRESSIONS: &[&str] = &[
+    "1 + 2 * 3 - 4 / 5",
+    "(-123) * (45.67)",
+    "-(1 - 3)",
+    r#""lox" + "script""#,
+    "1 < 2",
+    "2 <= 2",
+    "3 > 4",
+    "4 >= 5",
+    "1 == 1",
+    r#""a" != "a""#,
+    "nil == false",
+    "!nil",
+    "!!0",
+    "true == !false",
+    r#"1 + "one""#,
+    r#"-"one""#,
+    "nil < 1",
+    "(1, 2, 3)",
+    r#"(-"one", 2)"#,
+];
+
+#[test]
+fn vm_agrees_with_tree_walker() {
+    for source in EXPRESSIONS {
+        let expected = evaluate(source.parse::<Expr>().unwrap(), &mut vec![]);
+        let chunk = Compiler::new()
+            .compile(source.parse::<Expr>().unwrap())
+            .unwrap();
+        let actual = Vm::new(chunk).run();
+        match (expected, actual) {
+            (Ok(expected), Ok(actual)) => assert_eq!(expected, actual, "{source}"),
+            (Err(expected), Err(actual)) => {
+                assert_eq!(expected.to_string(), actual.to_string(), "{source}")
+            }
+            (expected, actual) => panic!("{source}: expected {expected:?}, got {actual:?}"),
+        }
+    }
+}